@@ -1,11 +1,12 @@
 use ark_crypto_primitives::snark::SNARK;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-use ark_circom::{read_zkey, CircomReduction, WitnessCalculator};
+use ark_circom::{read_zkey, CircomBuilder, CircomConfig, CircomReduction, WitnessCalculator};
 use ark_std::rand::thread_rng;
 
 use ark_bn254::{Bn254, Fr};
-use ark_groth16::Groth16;
+use ark_groth16::{r1cs_to_qap::R1CSToQAP, Groth16};
+use ark_poly::GeneralEvaluationDomain;
 use wasmer::Store;
 
 use std::{collections::HashMap, fs::File};
@@ -39,9 +40,26 @@ fn bench_groth(c: &mut Criterion, num_validators: u32, num_constraints: u32) {
     )
     .unwrap();
     let full_assignment = wtns
-        .calculate_witness_element::<Fr, _>(&mut store, inputs, false)
+        .calculate_witness_element::<Fr, _>(&mut store, inputs.clone(), false)
         .unwrap();
 
+    c.bench_function(&format!("witness {} {}", i, j), |b| {
+        b.iter(|| {
+            black_box(
+                wtns.calculate_witness_element::<Fr, _>(&mut store, inputs.clone(), false)
+                    .unwrap(),
+            )
+        })
+    });
+    c.bench_function(&format!("witness bigint {} {}", i, j), |b| {
+        b.iter(|| {
+            black_box(
+                wtns.calculate_witness(&mut store, inputs.clone(), false)
+                    .unwrap(),
+            )
+        })
+    });
+
     let mut rng = thread_rng();
     use ark_std::UniformRand;
     let rng = &mut rng;
@@ -61,11 +79,26 @@ fn bench_groth(c: &mut Criterion, num_validators: u32, num_constraints: u32) {
     .unwrap();
 
     let pvk = Groth16::<Bn254>::process_vk(&params.vk).unwrap();
-    let inputs = &full_assignment[1..num_inputs];
-    let verified = Groth16::<Bn254>::verify_with_processed_vk(&pvk, inputs, &proof).unwrap();
+    let public_inputs = &full_assignment[1..num_inputs];
+    let verified = Groth16::<Bn254>::verify_with_processed_vk(&pvk, public_inputs, &proof).unwrap();
 
     assert!(verified);
 
+    // run with `--features parallel` to compare against the rayon witness map
+    c.bench_function(&format!("witness map {} {}", i, j), |b| {
+        b.iter(|| {
+            black_box(
+                CircomReduction::witness_map_from_matrices::<Fr, GeneralEvaluationDomain<Fr>>(
+                    &matrices,
+                    num_inputs,
+                    num_constraints,
+                    full_assignment.as_slice(),
+                )
+                .unwrap(),
+            )
+        })
+    });
+
     c.bench_function(&format!("groth proof {} {}", i, j), |b| {
         b.iter(|| {
             black_box(
@@ -82,6 +115,30 @@ fn bench_groth(c: &mut Criterion, num_validators: u32, num_constraints: u32) {
             );
         })
     });
+
+    // end to end, from the inputs to the proof, going through the r1cs
+    let cfg = CircomConfig::<Fr>::new(
+        format!(
+            "./test-vectors/complex-circuit/complex-circuit-{}-{}.wasm",
+            i, j
+        ),
+        format!(
+            "./test-vectors/complex-circuit/complex-circuit-{}-{}.r1cs",
+            i, j
+        ),
+    )
+    .unwrap();
+    let mut builder = CircomBuilder::new(cfg);
+
+    c.bench_function(&format!("circom build {} {}", i, j), |b| {
+        b.iter(|| black_box(builder.build_with_inputs(inputs.clone()).unwrap()))
+    });
+    c.bench_function(&format!("circom prove {} {}", i, j), |b| {
+        b.iter(|| {
+            let circom = builder.build_with_inputs(inputs.clone()).unwrap();
+            black_box(Groth16::<Bn254, CircomReduction>::prove(&params, circom, rng).unwrap())
+        })
+    });
 }
 
 cfg_if::cfg_if! {
@@ -0,0 +1,93 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+#[cfg(feature = "parallel")]
+use ark_circom::{deserialize_g1_points_parallel, deserialize_g2_points_parallel};
+use ark_circom::{
+    deserialize_g1_points_sequential, deserialize_g2_points_sequential, read_zkey, read_zkey_vk,
+};
+
+use std::io::Cursor;
+
+// run with `--features parallel` to compare against the parallel point deserialization
+fn bench_zkey_load(c: &mut Criterion, num_validators: u32, num_constraints: u32) {
+    let i = num_validators;
+    let j = num_constraints;
+    let path = format!(
+        "./test-vectors/complex-circuit/complex-circuit-{}-{}.zkey",
+        i, j
+    );
+    // read the file upfront so that only the deserialization is measured
+    let data = std::fs::read(path).unwrap();
+
+    c.bench_function(&format!("zkey load {} {}", i, j), |b| {
+        b.iter(|| black_box(read_zkey(&mut Cursor::new(&data)).unwrap()))
+    });
+
+    // only the verifying key, for comparison with the full load
+    c.bench_function(&format!("zkey vk load {} {}", i, j), |b| {
+        b.iter(|| black_box(read_zkey_vk(&mut Cursor::new(&data)).unwrap()))
+    });
+}
+
+// Run with `--features parallel` to compare the paths. On a single core (release build,
+// x86_64) the parallel path never won, it only adds the overhead of rayon:
+//
+// | points    | g1 sequential | g1 parallel | g2 sequential | g2 parallel |
+// |-----------|---------------|-------------|---------------|-------------|
+// | 1 000     | 52 µs         | 58 µs       | 58 µs         | 72 µs       |
+// | 10 000    | 0.52 ms       | 0.51 ms     | 0.69 ms       | 0.81 ms     |
+// | 100 000   | 6.0 ms        | 7.1 ms      | 7.2 ms        | 10.6 ms     |
+// | 1 000 000 | 101 ms        | 143 ms      | 152 ms        | 255 ms      |
+//
+// The crossover on machines with several cores has not been measured yet.
+
+// Sequential and parallel parsing of the points of a section, side by side. The points are
+// not validated when loading, so any non-zero bytes parse like real points.
+fn bench_points(c: &mut Criterion) {
+    let mut group = c.benchmark_group("zkey points");
+    for num in [1_000, 10_000, 100_000, 1_000_000] {
+        let g1 = (0..num * 64).map(|i| i as u8 | 1).collect::<Vec<_>>();
+        let g2 = (0..num * 128).map(|i| i as u8 | 1).collect::<Vec<_>>();
+
+        group.bench_with_input(BenchmarkId::new("g1 sequential", num), &num, |b, &num| {
+            b.iter(|| black_box(deserialize_g1_points_sequential(&g1, num).unwrap()))
+        });
+        #[cfg(feature = "parallel")]
+        group.bench_with_input(BenchmarkId::new("g1 parallel", num), &num, |b, &num| {
+            b.iter(|| black_box(deserialize_g1_points_parallel(&g1, num).unwrap()))
+        });
+        group.bench_with_input(BenchmarkId::new("g2 sequential", num), &num, |b, &num| {
+            b.iter(|| black_box(deserialize_g2_points_sequential(&g2, num).unwrap()))
+        });
+        #[cfg(feature = "parallel")]
+        group.bench_with_input(BenchmarkId::new("g2 parallel", num), &num, |b, &num| {
+            b.iter(|| black_box(deserialize_g2_points_parallel(&g2, num).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "bench-complex-all")] {
+        const MIN_NUM_VARIABLES_POWER: u32 = 3;
+        const MAX_NUM_VARIABLES_POWER: u32 = 5;
+        const MAX_NUM_CONSTRAINTS_POWER: u32 = 5;
+        fn zkey_load_all(c: &mut Criterion) {
+            for i in MIN_NUM_VARIABLES_POWER..=MAX_NUM_VARIABLES_POWER {
+                for j in i..=MAX_NUM_CONSTRAINTS_POWER {
+                    let i = 10_u32.pow(i);
+                    let j = 10_u32.pow(j);
+                    bench_zkey_load(c, i, j);
+                }
+            }
+        }
+        criterion_group!(benches, zkey_load_all, bench_points);
+    } else {
+      fn zkey_load(c: &mut Criterion) {
+        bench_zkey_load(c, 10000, 10000);
+      }
+      criterion_group!(benches, zkey_load, bench_points);
+    }
+}
+
+criterion_main!(benches);
@@ -1,20 +1,35 @@
+use ark_ec::pairing::Pairing;
 use ark_ff::PrimeField;
+use ark_groth16::{Groth16, Proof, ProvingKey};
+use ark_std::rand::{CryptoRng, RngCore};
 use num_bigint::BigInt;
-use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek},
+    path::Path,
+    str::FromStr,
+};
 use wasmer::Store;
 
-use super::{CircomCircuit, R1CS};
+use super::{read_sym, CircomCircuit, CircomReduction, Symbol, R1CS};
 
+#[cfg(feature = "native")]
+use crate::witness::NativeWitnessCalculator;
 use crate::{
     circom::R1CSFile,
     witness::{Wasm, WitnessCalculator},
 };
-use color_eyre::Result;
+use color_eyre::{eyre::eyre, Result};
 
 #[derive(Debug)]
 pub struct CircomBuilder<F: PrimeField> {
     pub cfg: CircomConfig<F>,
     pub inputs: HashMap<String, Vec<BigInt>>,
+    /// Checks that the built circuits satisfy their constraints in release builds too,
+    /// instead of only in debug builds
+    pub check_constraints: bool,
 }
 
 // Add utils for creating this from files / directly from bytes
@@ -24,32 +39,109 @@ pub struct CircomConfig<F: PrimeField> {
     pub wtns: WitnessCalculator,
     pub store: Store,
     pub sanity_check: bool,
+    /// Symbols of the circuit, used to name its outputs
+    pub symbols: Option<Vec<Symbol>>,
+    /// Compiled witness generator used instead of the wasm one when set, see
+    /// [`with_native_witness`](Self::with_native_witness)
+    #[cfg(feature = "native")]
+    pub native: Option<NativeWitnessCalculator>,
 }
 
 impl<F: PrimeField> CircomConfig<F> {
+    /// Loads the wasm witness generator and the r1cs of a circuit. Fails if they do not
+    /// describe the same circuit, i.e. if their primes or numbers of wires differ.
     pub fn new(wtns: impl AsRef<Path>, r1cs: impl AsRef<Path>) -> Result<Self> {
         let mut store = Store::default();
-        let wtns = WitnessCalculator::new(&mut store, wtns).unwrap();
+        let wtns = WitnessCalculator::new(&mut store, wtns)?;
         let reader = BufReader::new(File::open(r1cs)?);
-        let r1cs = R1CSFile::new(reader)?.into();
-        Ok(Self {
-            wtns,
-            r1cs,
-            store,
-            sanity_check: false,
-        })
+        Self::from_parts(wtns, store, R1CSFile::new(reader)?)
+    }
+
+    /// Same as [`new`](Self::new), but also reads the `.sym` file produced by circom so
+    /// that the outputs of the built circuits can be named, see
+    /// [`CircomCircuit::get_named_outputs`]
+    pub fn new_with_sym(
+        wtns: impl AsRef<Path>,
+        r1cs: impl AsRef<Path>,
+        sym: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let mut cfg = Self::new(wtns, r1cs)?;
+        cfg.symbols = Some(read_sym(sym)?);
+        Ok(cfg)
+    }
+
+    /// Same as [`new`](Self::new), but takes the contents of the wasm and r1cs files
+    /// instead of their paths
+    pub fn from_bytes(wtns: &[u8], r1cs: &[u8]) -> Result<Self> {
+        let mut store = Store::default();
+        let wtns = WitnessCalculator::from_bytes(&mut store, wtns)?;
+        Self::from_parts(wtns, store, R1CSFile::new(Cursor::new(r1cs))?)
+    }
+
+    /// Same as [`from_bytes`](Self::from_bytes), but reads the wasm and r1cs from any
+    /// reader, e.g. an entry of an archive
+    pub fn from_readers(mut wtns: impl Read, r1cs: impl Read + Seek) -> Result<Self> {
+        let mut bytes = Vec::new();
+        wtns.read_to_end(&mut bytes)?;
+        let mut store = Store::default();
+        let wtns = WitnessCalculator::from_bytes(&mut store, bytes)?;
+        Self::from_parts(wtns, store, R1CSFile::new(r1cs)?)
+    }
+
+    /// Same as [`from_bytes`](Self::from_bytes), but downloads the wasm and r1cs files
+    /// from the given URLs first
+    #[cfg(feature = "fetch")]
+    pub async fn from_urls(wtns_url: &str, r1cs_url: &str) -> Result<Self> {
+        let wtns = crate::fetch::fetch(wtns_url).await?;
+        let r1cs = crate::fetch::fetch(r1cs_url).await?;
+        Self::from_bytes(&wtns, &r1cs)
     }
 
     pub fn new_from_wasm(wasm: Wasm, r1cs: impl AsRef<Path>) -> Result<Self> {
         let mut store = Store::default();
-        let wtns = WitnessCalculator::new_from_wasm(&mut store, wasm).unwrap();
+        let wtns = WitnessCalculator::new_from_wasm(&mut store, wasm)?;
         let reader = File::open(r1cs)?;
-        let r1cs = R1CSFile::new(reader)?.into();
+        Self::from_parts(wtns, store, R1CSFile::new(reader)?)
+    }
+
+    /// Calculates the witnesses of the built circuits with the native witness generator
+    /// at `binary`, compiled from the C++ that `circom --c` emits for the same circuit,
+    /// instead of with the wasm one. Both compute the same witness, but the native one is
+    /// much faster for large circuits.
+    #[cfg(feature = "native")]
+    pub fn with_native_witness(mut self, binary: impl AsRef<Path>) -> Self {
+        let native = NativeWitnessCalculator::new(binary).with_prime(self.wtns.prime.clone());
+        self.native = Some(native);
+        self
+    }
+
+    // Checks that the witness generator and the r1cs are for the same circuit, since mixing
+    // them up would otherwise only be caught by the sanity check of debug builds
+    fn from_parts(wtns: WitnessCalculator, mut store: Store, file: R1CSFile<F>) -> Result<Self> {
+        let prime = file.header.prime();
+        if wtns.prime != prime {
+            return Err(eyre!(
+                "the wasm is for the field of prime {}, but the r1cs for the one of prime {prime}",
+                wtns.prime
+            ));
+        }
+
+        let witness_size = wtns.witness_size(&mut store)?;
+        if witness_size != file.header.n_wires {
+            return Err(eyre!(
+                "the wasm computes witnesses of {witness_size} elements, but the r1cs has {} wires",
+                file.header.n_wires
+            ));
+        }
+
         Ok(Self {
             wtns,
-            r1cs,
+            r1cs: file.into(),
             store,
             sanity_check: false,
+            symbols: None,
+            #[cfg(feature = "native")]
+            native: None,
         })
     }
 }
@@ -61,15 +153,66 @@ impl<F: PrimeField> CircomBuilder<F> {
         Self {
             cfg,
             inputs: HashMap::new(),
+            check_constraints: false,
         }
     }
 
-    /// Pushes a Circom input at the specified name.
+    /// Pushes a Circom input at the specified name. The name is the one of the signal in
+    /// the main component, and each push appends the next element of an array signal, e.g.
+    /// pushing to `in` twice sets `in[0]` and then `in[1]`.
     pub fn push_input<T: Into<BigInt>>(&mut self, name: impl ToString, val: T) {
         let values = self.inputs.entry(name.to_string()).or_default();
         values.push(val.into());
     }
 
+    /// Pushes a (possibly multidimensional) array signal at the specified name, e.g.
+    /// `vec![vec![1, 2, 3], vec![4, 5, 6]]` for `signal input m[2][3]`. The values are
+    /// flattened in row-major order, which is the order circom's witness generator expects.
+    pub fn push_input_array<T: InputArray>(&mut self, name: impl ToString, vals: T) {
+        let values = self.inputs.entry(name.to_string()).or_default();
+        vals.flatten_into(values);
+    }
+
+    /// Pushes an input value of arbitrary nesting depth at the specified name, flattened in
+    /// row-major order like [`push_input_array`](Self::push_input_array) does
+    pub fn push_input_value(&mut self, name: impl ToString, value: InputValue) {
+        self.push_input_array(name, value);
+    }
+
+    /// Pushes all the inputs of a circom `input.json` file, see
+    /// [`push_inputs_from_json_str`](Self::push_inputs_from_json_str)
+    pub fn push_inputs_from_json(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let json = std::fs::read_to_string(path)?;
+        self.push_inputs_from_json_str(&json)
+    }
+
+    /// Pushes all the inputs of a JSON object mapping signal names to their values.
+    /// Values can be numbers, decimal or `0x`-prefixed hexadecimal strings, or (nested)
+    /// arrays of them for array signals, which are flattened in order.
+    pub fn push_inputs_from_json_str(&mut self, json: &str) -> Result<()> {
+        let json: HashMap<String, Value> = serde_json::from_str(json)?;
+
+        // parse everything before pushing so that an error leaves the inputs untouched
+        let mut inputs = Vec::with_capacity(json.len());
+        for (name, value) in json {
+            let mut values = Vec::new();
+            flatten_json_input(&value, &mut values)
+                .map_err(|err| eyre!("invalid value for input `{name}`: {err}"))?;
+            inputs.push((name, values));
+        }
+
+        for (name, values) in inputs {
+            self.inputs.entry(name).or_default().extend(values);
+        }
+        Ok(())
+    }
+
+    /// Removes all the previously pushed inputs, so that the builder can be reused
+    /// for another set of inputs
+    pub fn clear_inputs(&mut self) {
+        self.inputs.clear();
+    }
+
     /// Generates an empty circom circuit with no witness set, to be used for
     /// generation of the trusted setup parameters
     pub fn setup(&self) -> CircomCircuit<F> {
@@ -87,32 +230,556 @@ impl<F: PrimeField> CircomBuilder<F> {
     /// Creates the circuit populated with the witness corresponding to the previously
     /// provided inputs
     pub fn build(mut self) -> Result<CircomCircuit<F>> {
+        let inputs = std::mem::take(&mut self.inputs);
+        self.build_with_inputs(inputs)
+    }
+
+    /// Creates the circuit populated with the witness corresponding to the provided
+    /// inputs, ignoring the ones pushed to the builder. The builder is not consumed, so
+    /// the loaded witness calculator can be reused to build many circuits.
+    pub fn build_with_inputs<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        inputs: I,
+    ) -> Result<CircomCircuit<F>> {
         let mut circom = self.setup();
 
         // calculate the witness
-        let witness = self.cfg.wtns.calculate_witness_element::<F, _>(
+        let witness = self.calculate_witness(inputs)?;
+        circom.witness = Some(witness);
+
+        // sanity check, which is always done in debug builds
+        if self.check_constraints || cfg!(debug_assertions) {
+            circom.check_satisfied()?;
+        }
+
+        Ok(circom)
+    }
+
+    // runs the native witness generator if one is set, and the wasm one otherwise
+    fn calculate_witness<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        inputs: I,
+    ) -> Result<Vec<F>> {
+        #[cfg(feature = "native")]
+        if let Some(native) = &self.cfg.native {
+            let witness = native.calculate_witness(inputs)?;
+            return Ok(witness.iter().map(super::bigint_to_field).collect());
+        }
+        self.cfg.wtns.calculate_witness_element::<F, _>(
             &mut self.cfg.store,
-            self.inputs,
+            inputs,
             self.cfg.sanity_check,
-        )?;
-        circom.witness = Some(witness);
+        )
+    }
+
+    /// Builds the circuit for the previously provided inputs and proves it with `params`,
+    /// returning the proof along with the public inputs to verify it against. Use
+    /// [`build`](Self::build) to prove the circuit in another way.
+    pub fn prove<E, R>(self, params: &ProvingKey<E>, rng: &mut R) -> Result<(Proof<E>, Vec<F>)>
+    where
+        E: Pairing<ScalarField = F>,
+        R: RngCore + CryptoRng,
+    {
+        let r = F::rand(rng);
+        let s = F::rand(rng);
+        self.prove_deterministic(params, r, s)
+    }
+
+    /// Same as [`prove`](Self::prove), but with the blinding factors `r` and `s` given
+    /// instead of sampled, so that the same inputs always yield the same proof, e.g. for
+    /// snapshot tests. This is insecure outside of tests: two proofs for different
+    /// witnesses with the same `r` and `s` leak information about the witnesses.
+    pub fn prove_deterministic<E>(
+        self,
+        params: &ProvingKey<E>,
+        r: F,
+        s: F,
+    ) -> Result<(Proof<E>, Vec<F>)>
+    where
+        E: Pairing<ScalarField = F>,
+    {
+        let circom = self.build()?;
+        let inputs = circom
+            .get_public_inputs()
+            .ok_or_else(|| eyre!("the circuit has no witness"))?;
+        let proof =
+            Groth16::<E, CircomReduction>::create_proof_with_reduction(circom, params, r, s)?;
+        Ok((proof, inputs))
+    }
+}
+
+/// An input value or (nested) array of input values which can be passed to
+/// [`CircomBuilder::push_input_array`]
+pub trait InputArray {
+    /// Appends the values to `values` in row-major order
+    fn flatten_into(self, values: &mut Vec<BigInt>);
+}
 
-        // sanity check
-        debug_assert!({
-            use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
-            let cs = ConstraintSystem::<F>::new_ref();
-            circom.clone().generate_constraints(cs.clone()).unwrap();
-            let is_satisfied = cs.is_satisfied().unwrap();
-            if !is_satisfied {
-                println!(
-                    "Unsatisfied constraint: {:?}",
-                    cs.which_is_unsatisfied().unwrap()
-                );
+macro_rules! impl_input_array {
+    ($($t:ty),*) => {
+        $(
+            impl InputArray for $t {
+                fn flatten_into(self, values: &mut Vec<BigInt>) {
+                    values.push(self.into());
+                }
             }
+        )*
+    };
+}
 
-            is_satisfied
-        });
+impl_input_array!(
+    BigInt,
+    num_bigint::BigUint,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize
+);
 
-        Ok(circom)
+impl<T: InputArray> InputArray for Vec<T> {
+    fn flatten_into(self, values: &mut Vec<BigInt>) {
+        for value in self {
+            value.flatten_into(values);
+        }
+    }
+}
+
+impl<T: InputArray, const N: usize> InputArray for [T; N] {
+    fn flatten_into(self, values: &mut Vec<BigInt>) {
+        for value in self {
+            value.flatten_into(values);
+        }
+    }
+}
+
+/// The value of an input signal, which is either a single field element or a
+/// (multi-dimensional) array of them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputValue {
+    Scalar(BigInt),
+    Array(Vec<InputValue>),
+}
+
+impl InputValue {
+    /// Returns the dimensions of the value, e.g. `[2, 3]` for `m[2][3]` and `[]` for a
+    /// scalar, or `None` if the nested arrays do not all have the same shape
+    pub fn shape(&self) -> Option<Vec<usize>> {
+        match self {
+            InputValue::Scalar(_) => Some(Vec::new()),
+            InputValue::Array(values) => {
+                let mut shapes = values.iter().map(InputValue::shape);
+                let inner = shapes.next().unwrap_or(Some(Vec::new()))?;
+                if shapes.any(|shape| shape.as_ref() != Some(&inner)) {
+                    return None;
+                }
+                let mut shape = vec![values.len()];
+                shape.extend(inner);
+                Some(shape)
+            }
+        }
+    }
+
+    /// Parses a value of a circom `input.json`, i.e. a number, a decimal or `0x`-prefixed
+    /// hexadecimal string, or a (nested) array of them
+    pub fn from_json(value: &Value) -> Result<Self> {
+        Ok(match value {
+            Value::String(inner) => InputValue::Scalar(parse_integer(inner)?),
+            Value::Number(inner) => InputValue::Scalar(
+                inner
+                    .as_i64()
+                    .map(BigInt::from)
+                    .or_else(|| inner.as_u64().map(BigInt::from))
+                    .ok_or_else(|| eyre!("`{inner}` is not an integer"))?,
+            ),
+            Value::Array(inner) => InputValue::Array(
+                inner
+                    .iter()
+                    .map(InputValue::from_json)
+                    .collect::<Result<_>>()?,
+            ),
+            _ => return Err(eyre!("unsupported JSON type `{value}`")),
+        })
+    }
+}
+
+fn parse_integer(s: &str) -> Result<BigInt> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16)
+            .ok_or_else(|| eyre!("`{s}` is not a hexadecimal integer")),
+        None => BigInt::from_str(s).map_err(|_| eyre!("`{s}` is not a decimal integer")),
+    }
+}
+
+impl<T: Into<BigInt>> From<T> for InputValue {
+    fn from(value: T) -> Self {
+        InputValue::Scalar(value.into())
+    }
+}
+
+impl InputArray for InputValue {
+    fn flatten_into(self, values: &mut Vec<BigInt>) {
+        match self {
+            InputValue::Scalar(value) => values.push(value),
+            InputValue::Array(inner) => inner.flatten_into(values),
+        }
+    }
+}
+
+fn flatten_json_input(value: &Value, values: &mut Vec<BigInt>) -> Result<()> {
+    InputValue::from_json(value)?.flatten_into(values);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::UnsatisfiedConstraint;
+    use ark_bn254::Fr;
+
+    fn builder() -> CircomBuilder<Fr> {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        CircomBuilder::new(cfg)
+    }
+
+    #[tokio::test]
+    async fn inputs_from_json() {
+        let mut builder = builder();
+        builder
+            .push_inputs_from_json("./test-vectors/mycircuit-input1.json")
+            .unwrap();
+        assert_eq!(builder.inputs["a"], vec![BigInt::from(3)]);
+        assert_eq!(builder.inputs["b"], vec![BigInt::from(11)]);
+
+        let circom = builder.build().unwrap();
+        assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(33)]);
+    }
+
+    #[tokio::test]
+    async fn config_from_bytes() {
+        let wasm = std::fs::read("./test-vectors/mycircuit.wasm").unwrap();
+        let r1cs = std::fs::read("./test-vectors/mycircuit.r1cs").unwrap();
+        let cfg = CircomConfig::<Fr>::from_bytes(&wasm, &r1cs).unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+
+        let circom = builder.build().unwrap();
+        assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(33)]);
+    }
+
+    #[tokio::test]
+    async fn config_from_readers() {
+        let wasm = File::open("./test-vectors/mycircuit.wasm").unwrap();
+        let r1cs = File::open("./test-vectors/mycircuit.r1cs").unwrap();
+        let cfg = CircomConfig::<Fr>::from_readers(wasm, BufReader::new(r1cs)).unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+
+        let circom = builder.build().unwrap();
+        assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(33)]);
+    }
+
+    #[tokio::test]
+    async fn invalid_wasm() {
+        let err = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.r1cs",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap_err();
+        // the r1cs file is neither a wasm binary nor wat text
+        assert!(matches!(
+            err.downcast_ref::<wasmer::IoCompileError>(),
+            Some(wasmer::IoCompileError::Compile(_))
+        ));
+
+        let err = CircomConfig::<Fr>::new(
+            "./test-vectors/missing.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<wasmer::IoCompileError>(),
+            Some(wasmer::IoCompileError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[tokio::test]
+    async fn mismatched_circuits() {
+        let err = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/complex-circuit/complex-circuit-10000-10000.r1cs",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("wires"));
+    }
+
+    #[tokio::test]
+    async fn missing_wasm() {
+        let res = CircomConfig::<Fr>::new(
+            "./test-vectors/does-not-exist.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        );
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn named_outputs() {
+        let cfg = CircomConfig::<Fr>::new_with_sym(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+            "./test-vectors/mycircuit.sym",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+
+        let symbols = builder.cfg.symbols.clone().unwrap();
+        let circom = builder.build().unwrap();
+        let outputs = circom.get_named_outputs(&symbols).unwrap();
+        assert_eq!(
+            outputs,
+            HashMap::from([("c".to_string(), vec![Fr::from(33)])])
+        );
+    }
+
+    #[tokio::test]
+    async fn build_with_inputs() {
+        let mut builder = builder();
+        for (a, b) in [(3, 11), (5, 7)] {
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![BigInt::from(a)]),
+                ("b".to_string(), vec![BigInt::from(b)]),
+            ]);
+            let circom = builder.build_with_inputs(inputs).unwrap();
+            assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(a * b)]);
+        }
+
+        builder.push_input("a", 3);
+        builder.clear_inputs();
+        assert!(builder.inputs.is_empty());
+    }
+
+    #[cfg(all(unix, feature = "native"))]
+    #[tokio::test]
+    async fn native_witness() {
+        let mut wasm = builder();
+        let prime = wasm.cfg.wtns.prime.clone();
+        wasm.push_input("a", 3);
+        wasm.push_input("b", 11);
+        let expected = wasm.build().unwrap().witness.unwrap();
+
+        // stand in for the compiled generator with a script outputting the same witness
+        let witness = expected
+            .iter()
+            .map(|value| BigInt::from(num_bigint::BigUint::from(value.into_bigint())))
+            .collect::<Vec<_>>();
+        let (dir, binary) = crate::witness::fake_generator(&prime, &witness);
+
+        let mut native = builder();
+        native.cfg = native.cfg.with_native_witness(&binary);
+        native.push_input("a", 3);
+        native.push_input("b", 11);
+        let circom = native.build().unwrap();
+        assert_eq!(circom.witness.unwrap(), expected);
+
+        // the native generator is the one being run
+        let mut missing = builder();
+        missing.cfg = missing
+            .cfg
+            .with_native_witness(binary.with_file_name("missing"));
+        missing.push_input("a", 3);
+        missing.push_input("b", 11);
+        assert!(missing.build().is_err());
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn prove() {
+        use ark_bn254::Bn254;
+        use ark_std::rand::thread_rng;
+
+        let mut builder = builder();
+        let mut rng = thread_rng();
+        let params = Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(
+            builder.setup(),
+            &mut rng,
+        )
+        .unwrap();
+
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        let (proof, inputs) = builder.prove(&params, &mut rng).unwrap();
+        assert_eq!(inputs, vec![Fr::from(33)]);
+
+        let pvk = ark_groth16::prepare_verifying_key(&params.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &inputs).unwrap());
+    }
+
+    #[tokio::test]
+    async fn prove_deterministic() {
+        use ark_bn254::Bn254;
+        use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let params = Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(
+            builder().setup(),
+            &mut rng,
+        )
+        .unwrap();
+
+        let prove = |r, s| {
+            let mut builder = builder();
+            builder.push_input("a", 3);
+            builder.push_input("b", 11);
+            builder.prove_deterministic(&params, r, s).unwrap().0
+        };
+        let proof = prove(Fr::from(1), Fr::from(2));
+        assert_eq!(proof, prove(Fr::from(1), Fr::from(2)));
+        assert_ne!(proof, prove(Fr::from(1), Fr::from(3)));
+
+        let pvk = ark_groth16::prepare_verifying_key(&params.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &[Fr::from(33)]).unwrap());
+    }
+
+    #[tokio::test]
+    async fn check_constraints() {
+        let mut builder = builder();
+        builder.check_constraints = true;
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        assert!(builder.build_with_inputs(builder.inputs.clone()).is_ok());
+
+        // break the constraint a * b = c
+        builder.cfg.r1cs.constraints[0].2[0].1 += Fr::from(1);
+        let err = builder
+            .build_with_inputs(builder.inputs.clone())
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<UnsatisfiedConstraint>().unwrap().index,
+            0
+        );
+    }
+
+    #[test]
+    fn input_value() {
+        use InputValue::*;
+        let m = Array(vec![
+            Array(vec![1.into(), 2.into(), 3.into()]),
+            Array(vec![4.into(), 5.into(), 6.into()]),
+        ]);
+        assert_eq!(m.shape(), Some(vec![2, 3]));
+        assert_eq!(Scalar(3.into()).shape(), Some(vec![]));
+        assert_eq!(Array(vec![Array(vec![1.into()]), 2.into()]).shape(), None);
+
+        let json: Value = serde_json::from_str(r#"[["1", "2", "3"], [4, 5, 6]]"#).unwrap();
+        assert_eq!(InputValue::from_json(&json).unwrap(), m);
+
+        let json: Value = serde_json::from_str(r#"["0xff", "0XFF", "255"]"#).unwrap();
+        assert_eq!(
+            InputValue::from_json(&json).unwrap(),
+            Array(vec![255.into(), 255.into(), 255.into()])
+        );
+        assert!(InputValue::from_json(&Value::from("0xfg")).is_err());
+        assert!(InputValue::from_json(&Value::from("ff")).is_err());
+
+        let mut values = Vec::new();
+        m.flatten_into(&mut values);
+        assert_eq!(values, (1..=6).map(BigInt::from).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn input_array() {
+        let mut builder = builder();
+        builder.push_input_array("m", vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        builder.push_input_array("n", [[[1u8, 2], [3, 4]], [[5, 6], [7, 8]]]);
+        builder.push_input_array("a", BigInt::from(3));
+        assert_eq!(
+            builder.inputs["m"],
+            (1..=6).map(BigInt::from).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            builder.inputs["n"],
+            (1..=8).map(BigInt::from).collect::<Vec<_>>()
+        );
+        assert_eq!(builder.inputs["a"], vec![BigInt::from(3)]);
+
+        // must match the ordering used for `input.json` files, as snarkjs does
+        builder
+            .push_inputs_from_json_str(r#"{"o": [[1, 2, 3], [4, 5, 6]]}"#)
+            .unwrap();
+        assert_eq!(builder.inputs["o"], builder.inputs["m"]);
+    }
+
+    #[tokio::test]
+    async fn input_array_witness() {
+        // smtverifier10 takes `siblings[10]`, and its witness was calculated by circom from
+        // test-vectors/smtverifier10-input.json
+        let json = std::fs::read_to_string("./test-vectors/smtverifier10-input.json").unwrap();
+        let json: HashMap<String, Value> = serde_json::from_str(&json).unwrap();
+        let mut builder = builder();
+        builder.inputs.clear();
+        for (name, value) in &json {
+            match value {
+                Value::Array(siblings) => {
+                    let siblings = siblings
+                        .iter()
+                        .map(|v| v.as_str().unwrap().parse::<BigInt>().unwrap())
+                        .collect::<Vec<_>>();
+                    builder.push_input_array(name, siblings);
+                }
+                Value::String(v) => builder.push_input(name, v.parse::<BigInt>().unwrap()),
+                v => builder.push_input(name, v.as_u64().unwrap()),
+            }
+        }
+
+        let mut store = Store::default();
+        let mut wtns =
+            WitnessCalculator::new(&mut store, "./test-vectors/smtverifier10.wasm").unwrap();
+        let witness = wtns
+            .calculate_witness(&mut store, builder.inputs, true)
+            .unwrap();
+
+        let expected =
+            std::fs::read_to_string("./test-vectors/smtverifier10-witness.json").unwrap();
+        let expected: Vec<String> = serde_json::from_str(&expected).unwrap();
+        let expected = expected
+            .iter()
+            .map(|v| v.parse::<BigInt>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(witness, expected);
+    }
+
+    #[tokio::test]
+    async fn inputs_from_json_str() {
+        let mut builder = builder();
+        builder
+            .push_inputs_from_json_str(r#"{"a": "3", "m": [[1, "2"], [3, -4]]}"#)
+            .unwrap();
+        assert_eq!(builder.inputs["a"], vec![BigInt::from(3)]);
+        assert_eq!(
+            builder.inputs["m"],
+            [1, 2, 3, -4].map(BigInt::from).to_vec()
+        );
+
+        let err = builder
+            .push_inputs_from_json_str(r#"{"b": 11, "c": true}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("`c`"));
+        assert!(!builder.inputs.contains_key("b"));
     }
 }
@@ -1,12 +1,25 @@
 use ark_relations::r1cs::{
-    ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, LinearCombination,
+    SynthesisError, Variable,
 };
 
 use ark_ff::PrimeField;
 
-use super::R1CS;
+use super::{Symbol, R1CS};
+use std::collections::HashMap;
 
-use color_eyre::Result;
+use color_eyre::{eyre::eyre, Result};
+
+/// A constraint of the circuit which is not satisfied by its witness
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+#[error("the witness does not satisfy constraint {index} ({trace})")]
+pub struct UnsatisfiedConstraint {
+    /// Index of the constraint in the r1cs
+    pub index: usize,
+    /// The constraint as reported by arkworks' `which_is_unsatisfied`, i.e. its trace if
+    /// a `ConstraintLayer` is enabled and its index otherwise
+    pub trace: String,
+}
 
 #[derive(Clone, Debug)]
 pub struct CircomCircuit<F: PrimeField> {
@@ -15,19 +28,169 @@ pub struct CircomCircuit<F: PrimeField> {
 }
 
 impl<F: PrimeField> CircomCircuit<F> {
+    /// Returns the public inputs the circuit is proven with, i.e. the ones of
+    /// [`public_inputs_mapped`](Self::public_inputs_mapped) if the r1cs has a wire mapping,
+    /// and the ones of [`public_inputs_raw`](Self::public_inputs_raw) otherwise. This is the
+    /// one to verify proofs against.
     pub fn get_public_inputs(&self) -> Option<Vec<F>> {
-        match &self.witness {
-            None => None,
-            Some(w) => match &self.r1cs.wire_mapping {
-                None => Some(w[1..self.r1cs.num_inputs].to_vec()),
-                Some(m) => Some(m[1..self.r1cs.num_inputs].iter().map(|i| w[*i]).collect()),
-            },
+        self.public_inputs_mapped()
+            .or_else(|| self.public_inputs_raw())
+    }
+
+    /// Returns the elements of the witness following the constant 1, ignoring the wire
+    /// mapping. These are the public inputs of circuits without a wire mapping, such as the
+    /// ones returned by [`CircomBuilder::setup`](crate::CircomBuilder::setup).
+    pub fn public_inputs_raw(&self) -> Option<Vec<F>> {
+        let w = self.witness.as_ref()?;
+        Some(w[1..self.r1cs.num_inputs].to_vec())
+    }
+
+    /// Returns the public inputs through the wire mapping of the r1cs, which is how they are
+    /// allocated when synthesizing circuits returned by
+    /// [`CircomBuilder::build`](crate::CircomBuilder::build). Returns `None` if the circuit
+    /// has no witness or no wire mapping.
+    pub fn public_inputs_mapped(&self) -> Option<Vec<F>> {
+        let w = self.witness.as_ref()?;
+        let m = self.r1cs.wire_mapping.as_ref()?;
+        Some(m[1..self.r1cs.num_inputs].iter().map(|i| w[*i]).collect())
+    }
+
+    /// Splits the witness into the assignments of the instance variables, starting with the
+    /// constant 1, and of the witness variables, in the order arkworks allocates them.
+    /// Concatenated, they are the `full_assignment` taken by
+    /// `Groth16::create_proof_with_reduction_and_matrices`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the circuit has no witness, e.g. if it was returned by
+    /// [`CircomBuilder::setup`](crate::CircomBuilder::setup).
+    pub fn into_assignments(self) -> (Vec<F>, Vec<F>) {
+        let w = self.witness.expect("the circuit has no witness");
+        let mut assignment: Vec<F> = match &self.r1cs.wire_mapping {
+            None => w,
+            Some(m) => m[..self.r1cs.num_inputs + self.r1cs.num_aux]
+                .iter()
+                .map(|i| w[*i])
+                .collect(),
+        };
+        assignment.truncate(self.r1cs.num_inputs + self.r1cs.num_aux);
+        let aux = assignment.split_off(self.r1cs.num_inputs);
+        (assignment, aux)
+    }
+
+    /// Checks that `claimed` matches the public inputs of the witness, so that a proof is
+    /// not created or verified against inconsistent public inputs
+    pub fn assert_public_inputs_match(&self, claimed: &[F]) -> Result<()> {
+        let inputs = self
+            .get_public_inputs()
+            .ok_or_else(|| eyre!("the circuit has no witness"))?;
+        if inputs.len() != claimed.len() {
+            return Err(eyre!(
+                "expected {} public inputs, got {}",
+                inputs.len(),
+                claimed.len()
+            ));
         }
+        match inputs.iter().zip(claimed).position(|(a, b)| a != b) {
+            Some(i) => Err(eyre!(
+                "public input {i} does not match the witness: expected {}, got {}",
+                inputs[i],
+                claimed[i]
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the values of the output signals of the main component, keyed by signal
+    /// name. Array signals such as `out[4]` are returned under their base name `out`, with
+    /// their elements in the order they appear in the witness.
+    pub fn get_named_outputs(&self, symbols: &[Symbol]) -> Option<HashMap<String, Vec<F>>> {
+        let w = self.witness.as_ref()?;
+
+        let mut outputs = symbols
+            .iter()
+            .filter_map(|symbol| {
+                let wire = symbol
+                    .wire
+                    .filter(|w| (1..=self.r1cs.num_outputs).contains(w))?;
+                let name = symbol.name.strip_prefix("main.")?;
+                let name = name.split('[').next().unwrap_or(name);
+                Some((wire, name))
+            })
+            .collect::<Vec<_>>();
+        outputs.sort_unstable_by_key(|(wire, _)| *wire);
+
+        let mut named = HashMap::<String, Vec<F>>::new();
+        for (wire, name) in outputs {
+            let value = match &self.r1cs.wire_mapping {
+                None => w[wire],
+                Some(m) => w[m[wire]],
+            };
+            named.entry(name.to_string()).or_default().push(value);
+        }
+        Some(named)
     }
 }
 
-impl<F: PrimeField> ConstraintSynthesizer<F> for CircomCircuit<F> {
-    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+#[cfg(any(feature = "ethereum", feature = "ethereum-alloy"))]
+impl CircomCircuit<ark_bn254::Fr> {
+    /// Returns the public inputs in the format expected by the Solidity verifier, with the
+    /// U256 of either ethers or alloy
+    pub fn ethereum_inputs<U: crate::ethereum::Uint256>(
+        &self,
+    ) -> Option<crate::ethereum::Inputs<U>> {
+        self.get_public_inputs()
+            .map(|inputs| crate::ethereum::Inputs::from(&inputs[..]))
+    }
+}
+
+impl<F: PrimeField> CircomCircuit<F> {
+    /// Checks that the witness satisfies every constraint of the circuit, synthesizing
+    /// it by reference so that the constraints do not need to be cloned.
+    pub fn check_satisfied_ref(&self) -> Result<bool, SynthesisError> {
+        Ok(self.unsatisfied_constraint()?.is_none())
+    }
+
+    /// Checks that the witness satisfies every constraint of the circuit, returning the
+    /// first one which it does not satisfy otherwise
+    pub fn check_satisfied(&self) -> Result<(), UnsatisfiedConstraint> {
+        let value = |wire: usize| match (&self.witness, &self.r1cs.wire_mapping) {
+            (None, _) => F::one(),
+            (Some(w), Some(m)) => w[m[wire]],
+            (Some(w), None) => w[wire],
+        };
+        let eval = |lc: &[(usize, F)]| {
+            lc.iter()
+                .map(|(wire, coeff)| *coeff * value(*wire))
+                .sum::<F>()
+        };
+
+        match self
+            .r1cs
+            .constraints
+            .iter()
+            .position(|(a, b, c)| eval(a) * eval(b) != eval(c))
+        {
+            Some(index) => {
+                let trace = self
+                    .unsatisfied_constraint()
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| index.to_string());
+                Err(UnsatisfiedConstraint { index, trace })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the name of the first unsatisfied constraint, if any
+    pub(crate) fn unsatisfied_constraint(&self) -> Result<Option<String>, SynthesisError> {
+        let cs = ConstraintSystem::<F>::new_ref();
+        self.synthesize(cs.clone())?;
+        cs.which_is_unsatisfied()
+    }
+
+    fn synthesize(&self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
         let witness = &self.witness;
         let wire_mapping = &self.r1cs.wire_mapping;
 
@@ -82,13 +245,20 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for CircomCircuit<F> {
     }
 }
 
+impl<F: PrimeField> ConstraintSynthesizer<F> for CircomCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.synthesize(cs)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "wasmer")]
     use crate::{CircomBuilder, CircomConfig};
     use ark_bn254::Fr;
-    use ark_relations::r1cs::ConstraintSystem;
 
+    #[cfg(feature = "wasmer")]
     #[tokio::test]
     async fn satisfied() {
         let cfg = CircomConfig::<Fr>::new(
@@ -105,4 +275,221 @@ mod tests {
         circom.generate_constraints(cs.clone()).unwrap();
         assert!(cs.is_satisfied().unwrap());
     }
+
+    #[cfg(feature = "wasmer")]
+    #[tokio::test]
+    async fn built_assignments() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        let circom = builder.build().unwrap();
+
+        // the split the zkey tests do by hand on the full witness
+        let full_assignment = circom.witness.clone().unwrap();
+        let num_inputs = circom.r1cs.num_inputs;
+        let public_inputs = full_assignment[1..num_inputs].to_vec();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circom.clone().generate_constraints(cs.clone()).unwrap();
+        let cs = cs.into_inner().unwrap();
+
+        let (instance, aux) = circom.into_assignments();
+        assert_eq!(instance, cs.instance_assignment);
+        assert_eq!(aux, cs.witness_assignment);
+        assert_eq!(instance[1..], public_inputs);
+        assert_eq!(instance, [1, 33].map(Fr::from));
+    }
+
+    #[test]
+    fn satisfied_ref() {
+        // c = a * b, with c public
+        let mut circom = CircomCircuit {
+            r1cs: R1CS {
+                num_inputs: 2,
+                num_outputs: 1,
+                num_aux: 2,
+                num_variables: 4,
+                constraints: vec![(
+                    vec![(2, Fr::from(1))],
+                    vec![(3, Fr::from(1))],
+                    vec![(1, Fr::from(1))],
+                )],
+                wire_mapping: None,
+            },
+            witness: Some(vec![Fr::from(1), Fr::from(33), Fr::from(3), Fr::from(11)]),
+        };
+        assert!(circom.check_satisfied_ref().unwrap());
+
+        circom.witness = Some(vec![Fr::from(1), Fr::from(34), Fr::from(3), Fr::from(11)]);
+        assert!(!circom.check_satisfied_ref().unwrap());
+    }
+
+    #[test]
+    fn unsatisfied_constraint() {
+        // c = a * b and d = a + b, with c and d public
+        let mut circom = CircomCircuit {
+            r1cs: R1CS {
+                num_inputs: 3,
+                num_outputs: 2,
+                num_aux: 2,
+                num_variables: 5,
+                constraints: vec![
+                    (
+                        vec![(3, Fr::from(1))],
+                        vec![(4, Fr::from(1))],
+                        vec![(1, Fr::from(1))],
+                    ),
+                    (
+                        vec![(3, Fr::from(1)), (4, Fr::from(1))],
+                        vec![(0, Fr::from(1))],
+                        vec![(2, Fr::from(1))],
+                    ),
+                ],
+                wire_mapping: None,
+            },
+            witness: Some([1, 33, 14, 3, 11].map(Fr::from).to_vec()),
+        };
+        circom.check_satisfied().unwrap();
+
+        circom.witness = Some([1, 33, 15, 3, 11].map(Fr::from).to_vec());
+        let err = circom.check_satisfied().unwrap_err();
+        assert_eq!(err.index, 1);
+        assert_eq!(err.trace, "1");
+    }
+
+    #[test]
+    fn assignments() {
+        let mut circom = CircomCircuit {
+            r1cs: R1CS {
+                num_inputs: 2,
+                num_outputs: 1,
+                num_aux: 2,
+                num_variables: 4,
+                constraints: vec![],
+                wire_mapping: None,
+            },
+            witness: Some(vec![Fr::from(1), Fr::from(33), Fr::from(3), Fr::from(11)]),
+        };
+        let full_assignment = circom.witness.clone().unwrap();
+        let num_inputs = circom.r1cs.num_inputs;
+        let (instance, aux) = circom.clone().into_assignments();
+        assert_eq!(instance, full_assignment[..num_inputs]);
+        assert_eq!(aux, full_assignment[num_inputs..]);
+        assert_eq!(instance[1..], circom.get_public_inputs().unwrap());
+
+        // wire 1 maps to w[2], the third element of the witness
+        circom.r1cs.wire_mapping = Some(vec![0, 2, 1, 3]);
+        let (instance, aux) = circom.into_assignments();
+        assert_eq!(instance, vec![Fr::from(1), Fr::from(3)]);
+        assert_eq!(aux, vec![Fr::from(33), Fr::from(11)]);
+    }
+
+    #[test]
+    fn public_inputs_mapping() {
+        let mut circom = CircomCircuit {
+            r1cs: R1CS {
+                num_inputs: 2,
+                num_outputs: 1,
+                num_aux: 2,
+                num_variables: 4,
+                constraints: vec![],
+                wire_mapping: None,
+            },
+            witness: Some(vec![Fr::from(1), Fr::from(33), Fr::from(3), Fr::from(11)]),
+        };
+        assert_eq!(circom.public_inputs_raw().unwrap(), vec![Fr::from(33)]);
+        assert_eq!(circom.public_inputs_mapped(), None);
+        assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(33)]);
+
+        // wire 1 is the third element of the witness
+        circom.r1cs.wire_mapping = Some(vec![0, 2, 1, 3]);
+        assert_eq!(circom.public_inputs_raw().unwrap(), vec![Fr::from(33)]);
+        assert_eq!(circom.public_inputs_mapped().unwrap(), vec![Fr::from(3)]);
+        assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(3)]);
+
+        circom.witness = None;
+        assert_eq!(circom.public_inputs_raw(), None);
+        assert_eq!(circom.public_inputs_mapped(), None);
+    }
+
+    #[test]
+    fn public_inputs_match() {
+        let mut circom = CircomCircuit {
+            r1cs: R1CS {
+                num_inputs: 3,
+                num_outputs: 1,
+                num_aux: 1,
+                num_variables: 4,
+                constraints: vec![],
+                wire_mapping: None,
+            },
+            witness: Some(vec![Fr::from(1), Fr::from(33), Fr::from(3), Fr::from(11)]),
+        };
+        circom
+            .assert_public_inputs_match(&[Fr::from(33), Fr::from(3)])
+            .unwrap();
+
+        let err = circom
+            .assert_public_inputs_match(&[Fr::from(33), Fr::from(4)])
+            .unwrap_err();
+        assert!(err.to_string().contains("public input 1"));
+        assert!(circom.assert_public_inputs_match(&[Fr::from(33)]).is_err());
+
+        circom.witness = None;
+        assert!(circom.assert_public_inputs_match(&[]).is_err());
+    }
+
+    #[test]
+    fn named_outputs() {
+        let circom = CircomCircuit {
+            r1cs: R1CS {
+                num_inputs: 4,
+                num_outputs: 3,
+                num_aux: 1,
+                num_variables: 5,
+                constraints: vec![],
+                wire_mapping: None,
+            },
+            witness: Some((0..5).map(Fr::from).collect()),
+        };
+        let symbols = crate::circom::sym_reader::read_sym_from(
+            "1,3,0,main.out_b[1]\n2,2,0,main.out_b[0]\n3,1,0,main.out_a\n4,4,0,main.in\n5,-1,1,main.sub.out\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        let outputs = circom.get_named_outputs(&symbols).unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs["out_a"], vec![Fr::from(1)]);
+        assert_eq!(outputs["out_b"], vec![Fr::from(2), Fr::from(3)]);
+    }
+
+    #[test]
+    #[cfg(feature = "ethereum")]
+    fn ethereum_inputs() {
+        let circom = CircomCircuit {
+            r1cs: R1CS {
+                num_inputs: 2,
+                num_outputs: 1,
+                num_aux: 1,
+                num_variables: 3,
+                constraints: vec![],
+                wire_mapping: None,
+            },
+            witness: Some(vec![Fr::from(1), Fr::from(33), Fr::from(3)]),
+        };
+        let inputs = circom.ethereum_inputs::<crate::ethereum::U256>().unwrap();
+        assert_eq!(inputs.0, vec![33.into()]);
+
+        let circom = CircomCircuit {
+            witness: None,
+            ..circom
+        };
+        assert!(circom.ethereum_inputs::<crate::ethereum::U256>().is_none());
+    }
 }
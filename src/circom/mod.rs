@@ -1,14 +1,53 @@
+use ark_ff::PrimeField;
+use num_bigint::{BigInt, Sign};
+
 pub mod r1cs_reader;
 pub use r1cs_reader::{R1CSFile, R1CS};
 
+pub mod sym_reader;
+pub use sym_reader::{read_sym, Symbol};
+
 mod circuit;
-pub use circuit::CircomCircuit;
+pub use circuit::{CircomCircuit, UnsatisfiedConstraint};
 
+#[cfg(feature = "wasmer")]
 mod builder;
-pub use builder::{CircomBuilder, CircomConfig};
+#[cfg(feature = "wasmer")]
+pub use builder::{CircomBuilder, CircomConfig, InputArray, InputValue};
 
 mod qap;
-pub use qap::CircomReduction;
+/// The default witness map of arkworks, see [`CircomReduction`] for when to use which
+pub use ark_groth16::r1cs_to_qap::LibsnarkReduction;
+pub use qap::{qap_witness, CircomReduction, QapWitness};
 
 pub type Constraints<F> = (ConstraintVec<F>, ConstraintVec<F>, ConstraintVec<F>);
 pub type ConstraintVec<F> = Vec<(usize, F)>;
+
+/// Converts a value computed by circom, e.g. an element of the witness, to a field
+/// element. Negative values are mapped to their additive inverse and values larger than
+/// the modulus are reduced, like circom does.
+pub fn bigint_to_field<F: PrimeField>(value: &BigInt) -> F {
+    let element = F::from(value.magnitude().clone());
+    if value.sign() == Sign::Minus {
+        -element
+    } else {
+        element
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn bigint_conversion() {
+        let modulus = BigInt::from(BigUint::from(Fr::MODULUS));
+        assert_eq!(bigint_to_field::<Fr>(&BigInt::from(33)), Fr::from(33));
+        assert_eq!(bigint_to_field::<Fr>(&BigInt::from(-1)), -Fr::from(1));
+        assert_eq!(bigint_to_field::<Fr>(&(&modulus + 5)), Fr::from(5));
+        assert_eq!(bigint_to_field::<Fr>(&(-&modulus - 5)), -Fr::from(5));
+        assert_eq!(bigint_to_field::<Fr>(&modulus), Fr::from(0));
+    }
+}
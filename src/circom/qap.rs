@@ -3,14 +3,63 @@ use ark_groth16::r1cs_to_qap::{evaluate_constraint, LibsnarkReduction, R1CSToQAP
 use ark_poly::EvaluationDomain;
 use ark_relations::r1cs::{ConstraintMatrices, ConstraintSystemRef, SynthesisError};
 use ark_std::{cfg_into_iter, cfg_iter, cfg_iter_mut, vec};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 /// Implements the witness map used by snarkjs. The arkworks witness map calculates the
 /// coefficients of H through computing (AB-C)/Z in the evaluation domain and going back to the
 /// coefficients domain. snarkjs instead precomputes the Lagrange form of the powers of tau bases
 /// in a domain twice as large and the witness map is computed as the odd coefficients of (AB-C)
 /// in that domain. This serves as HZ when computing the C proof element.
+///
+/// The public inputs are also laid out differently: they are appended to the evaluations of A
+/// after the constraints, instead of being added as extra constraints. Proving keys of snarkjs
+/// therefore only work with this reduction, and [`qap_witness`] returns its intermediate
+/// values for debugging proofs which do not verify.
+///
+/// Proofs created with arkworks' default [`LibsnarkReduction`] only verify with keys set up
+/// with that reduction too, e.g. by `Groth16::<Bn254>::setup`, and never with a zkey.
 pub struct CircomReduction;
 
+/// The intermediate values of the witness map of [`CircomReduction`], for comparing them
+/// with the ones computed by snarkjs when a proof does not verify
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QapWitness<F> {
+    /// The evaluations of A over the domain: one per constraint, followed by the public
+    /// inputs (including the constant 1), padded with zeros to the size of the domain
+    pub a: Vec<F>,
+    /// The evaluations of B over the domain, padded with zeros
+    pub b: Vec<F>,
+    /// The evaluations of C over the domain, computed like snarkjs as `a * b` for each
+    /// constraint rather than from the C matrix
+    pub c: Vec<F>,
+    /// The odd coefficients of `A * B - C` in the domain twice as large, i.e. the values
+    /// the prover multiplies with the H query of the proving key
+    pub h: Vec<F>,
+}
+
+/// Computes the witness map of [`CircomReduction`] for a full assignment of the
+/// variables, keeping the evaluations of A, B and C along with H. `D` is the evaluation
+/// domain used by the prover, e.g. `GeneralEvaluationDomain<Fr>`.
+pub fn qap_witness<F: PrimeField, D: EvaluationDomain<F>>(
+    matrices: &ConstraintMatrices<F>,
+    num_inputs: usize,
+    num_constraints: usize,
+    full_assignment: &[F],
+) -> Result<QapWitness<F>, SynthesisError> {
+    let domain =
+        D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+    let (a, b, c) = evaluate_constraints(
+        &domain,
+        matrices,
+        num_inputs,
+        num_constraints,
+        full_assignment,
+    );
+    let h = quotient::<F, D>(&domain, a.clone(), b.clone(), c.clone())?;
+    Ok(QapWitness { a, b, c, h })
+}
+
 impl R1CSToQAP for CircomReduction {
     #[allow(clippy::type_complexity)]
     fn instance_map_with_evaluation<F: PrimeField, D: EvaluationDomain<F>>(
@@ -20,71 +69,24 @@ impl R1CSToQAP for CircomReduction {
         LibsnarkReduction::instance_map_with_evaluation::<F, D>(cs, t)
     }
 
+    /// With the `parallel` feature, the constraints are evaluated and the odd coefficients are
+    /// computed with rayon.
     fn witness_map_from_matrices<F: PrimeField, D: EvaluationDomain<F>>(
         matrices: &ConstraintMatrices<F>,
         num_inputs: usize,
         num_constraints: usize,
         full_assignment: &[F],
     ) -> Result<Vec<F>, SynthesisError> {
-        let zero = F::zero();
         let domain =
             D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
-        let domain_size = domain.size();
-
-        let mut a = vec![zero; domain_size];
-        let mut b = vec![zero; domain_size];
-
-        cfg_iter_mut!(a[..num_constraints])
-            .zip(cfg_iter_mut!(b[..num_constraints]))
-            .zip(cfg_iter!(&matrices.a))
-            .zip(cfg_iter!(&matrices.b))
-            .for_each(|(((a, b), at_i), bt_i)| {
-                *a = evaluate_constraint(at_i, full_assignment);
-                *b = evaluate_constraint(bt_i, full_assignment);
-            });
-
-        {
-            let start = num_constraints;
-            let end = start + num_inputs;
-            a[start..end].clone_from_slice(&full_assignment[..num_inputs]);
-        }
-
-        let mut c = vec![zero; domain_size];
-        cfg_iter_mut!(c[..num_constraints])
-            .zip(&a)
-            .zip(&b)
-            .for_each(|((c_i, &a), &b)| {
-                *c_i = a * b;
-            });
-
-        domain.ifft_in_place(&mut a);
-        domain.ifft_in_place(&mut b);
-
-        let root_of_unity = {
-            let domain_size_double = 2 * domain_size;
-            let domain_double =
-                D::new(domain_size_double).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
-            domain_double.element(1)
-        };
-        D::distribute_powers_and_mul_by_const(&mut a, root_of_unity, F::one());
-        D::distribute_powers_and_mul_by_const(&mut b, root_of_unity, F::one());
-
-        domain.fft_in_place(&mut a);
-        domain.fft_in_place(&mut b);
-
-        let mut ab = domain.mul_polynomials_in_evaluation_domain(&a, &b);
-        drop(a);
-        drop(b);
-
-        domain.ifft_in_place(&mut c);
-        D::distribute_powers_and_mul_by_const(&mut c, root_of_unity, F::one());
-        domain.fft_in_place(&mut c);
-
-        cfg_iter_mut!(ab)
-            .zip(c)
-            .for_each(|(ab_i, c_i)| *ab_i -= &c_i);
-
-        Ok(ab)
+        let (a, b, c) = evaluate_constraints(
+            &domain,
+            matrices,
+            num_inputs,
+            num_constraints,
+            full_assignment,
+        );
+        quotient::<F, D>(&domain, a, b, c)
     }
 
     fn h_query_scalars<F: PrimeField, D: EvaluationDomain<F>>(
@@ -104,3 +106,191 @@ impl R1CSToQAP for CircomReduction {
         Ok(cfg_into_iter!(scalars).skip(1).step_by(2).collect())
     }
 }
+
+// Evaluates A, B and C over the domain. Unlike the arkworks default, the public inputs are
+// appended to A after the constraints rather than added as constraints.
+#[allow(clippy::type_complexity)]
+fn evaluate_constraints<F: PrimeField, D: EvaluationDomain<F>>(
+    domain: &D,
+    matrices: &ConstraintMatrices<F>,
+    num_inputs: usize,
+    num_constraints: usize,
+    full_assignment: &[F],
+) -> (Vec<F>, Vec<F>, Vec<F>) {
+    let zero = F::zero();
+    let domain_size = domain.size();
+
+    let mut a = vec![zero; domain_size];
+    let mut b = vec![zero; domain_size];
+
+    cfg_iter_mut!(a[..num_constraints])
+        .zip(cfg_iter_mut!(b[..num_constraints]))
+        .zip(cfg_iter!(&matrices.a))
+        .zip(cfg_iter!(&matrices.b))
+        .for_each(|(((a, b), at_i), bt_i)| {
+            *a = evaluate_constraint(at_i, full_assignment);
+            *b = evaluate_constraint(bt_i, full_assignment);
+        });
+
+    {
+        let start = num_constraints;
+        let end = start + num_inputs;
+        a[start..end].clone_from_slice(&full_assignment[..num_inputs]);
+    }
+
+    let mut c = vec![zero; domain_size];
+    cfg_iter_mut!(c[..num_constraints])
+        .zip(&a)
+        .zip(&b)
+        .for_each(|((c_i, &a), &b)| {
+            *c_i = a * b;
+        });
+
+    (a, b, c)
+}
+
+// Computes the odd coefficients of A * B - C in the domain twice as large, by evaluating
+// the polynomials over the coset shifted by a root of unity of that domain
+fn quotient<F: PrimeField, D: EvaluationDomain<F>>(
+    domain: &D,
+    mut a: Vec<F>,
+    mut b: Vec<F>,
+    mut c: Vec<F>,
+) -> Result<Vec<F>, SynthesisError> {
+    domain.ifft_in_place(&mut a);
+    domain.ifft_in_place(&mut b);
+
+    let root_of_unity = {
+        let domain_size_double = 2 * domain.size();
+        let domain_double =
+            D::new(domain_size_double).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        domain_double.element(1)
+    };
+    D::distribute_powers_and_mul_by_const(&mut a, root_of_unity, F::one());
+    D::distribute_powers_and_mul_by_const(&mut b, root_of_unity, F::one());
+
+    domain.fft_in_place(&mut a);
+    domain.fft_in_place(&mut b);
+
+    let mut ab = domain.mul_polynomials_in_evaluation_domain(&a, &b);
+    drop(a);
+    drop(b);
+
+    domain.ifft_in_place(&mut c);
+    D::distribute_powers_and_mul_by_const(&mut c, root_of_unity, F::one());
+    domain.fft_in_place(&mut c);
+
+    cfg_iter_mut!(ab)
+        .zip(c)
+        .for_each(|(ab_i, c_i)| *ab_i -= &c_i);
+
+    Ok(ab)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mycircuit_proof;
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::{prepare_verifying_key, Groth16};
+    use ark_poly::GeneralEvaluationDomain;
+    use ark_std::rand::thread_rng;
+    use std::fs::File;
+
+    #[test]
+    fn witness_map() {
+        let (params, matrices, full_assignment, _) = mycircuit_proof();
+        let num_inputs = matrices.num_instance_variables;
+        let num_constraints = matrices.num_constraints;
+        let qap = qap_witness::<Fr, GeneralEvaluationDomain<Fr>>(
+            &matrices,
+            num_inputs,
+            num_constraints,
+            &full_assignment,
+        )
+        .unwrap();
+
+        // the single constraint, which circom writes as -a * b = -c, followed by the
+        // public inputs
+        assert_eq!(qap.a.len(), 4);
+        assert_eq!(qap.a[..3], [-Fr::from(3), Fr::from(1), Fr::from(33)]);
+        assert_eq!(qap.b[0], Fr::from(11));
+        assert_eq!(qap.c[0], -Fr::from(33));
+
+        let h = CircomReduction::witness_map_from_matrices::<Fr, GeneralEvaluationDomain<Fr>>(
+            &matrices,
+            num_inputs,
+            num_constraints,
+            &full_assignment,
+        )
+        .unwrap();
+        assert_eq!(qap.h, h);
+
+        // H is what makes the proof verify
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &params,
+            Fr::from(1),
+            Fr::from(2),
+            &matrices,
+            num_inputs,
+            num_constraints,
+            &full_assignment,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &[Fr::from(33)]).unwrap());
+    }
+
+    #[test]
+    fn reductions() {
+        use crate::circom::{CircomCircuit, R1CSFile};
+        use ark_groth16::ProvingKey;
+
+        // mycircuit for a = 3, b = 11, from its r1cs rather than a zkey, whose matrices
+        // lack the public input constraints of the default reduction. The witness is given
+        // in the order of the wires, so the mapping to signals is dropped.
+        let file = File::open("./test-vectors/mycircuit.r1cs").unwrap();
+        let mut circom = CircomCircuit {
+            r1cs: R1CSFile::<Fr>::new(file).unwrap().into(),
+            witness: Some([1, 33, 3, 11].map(Fr::from).to_vec()),
+        };
+        circom.r1cs.wire_mapping = None;
+        let rng = &mut thread_rng();
+        let verifies = |params: &ProvingKey<Bn254>, proof| {
+            let pvk = prepare_verifying_key(&params.vk);
+            Groth16::<Bn254>::verify_proof(&pvk, &proof, &[Fr::from(33)]).unwrap()
+        };
+
+        let circom_params =
+            Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(
+                circom.clone(),
+                rng,
+            )
+            .unwrap();
+        let libsnark_params =
+            Groth16::<Bn254, LibsnarkReduction>::generate_random_parameters_with_reduction(
+                circom.clone(),
+                rng,
+            )
+            .unwrap();
+
+        // proofs only verify when created with the reduction their key was set up with
+        for (params, circom_verifies) in [(&circom_params, true), (&libsnark_params, false)] {
+            let proof = Groth16::<Bn254, CircomReduction>::create_random_proof_with_reduction(
+                circom.clone(),
+                params,
+                rng,
+            )
+            .unwrap();
+            assert_eq!(verifies(params, proof), circom_verifies);
+
+            let proof = Groth16::<Bn254, LibsnarkReduction>::create_random_proof_with_reduction(
+                circom.clone(),
+                params,
+                rng,
+            )
+            .unwrap();
+            assert_eq!(verifies(params, proof), !circom_verifies);
+        }
+    }
+}
@@ -6,7 +6,9 @@ use byteorder::{LittleEndian, ReadBytesExt};
 use std::io::{Error, ErrorKind};
 
 use ark_serialize::{SerializationError, SerializationError::IoError};
-use ark_std::io::{Read, Seek, SeekFrom};
+use ark_std::io::{Read, Seek, SeekFrom, Take};
+use num_bigint::{BigInt, BigUint};
+use sha2::{Digest, Sha256};
 
 use std::collections::HashMap;
 
@@ -17,12 +19,35 @@ use super::{ConstraintVec, Constraints};
 #[derive(Clone, Debug)]
 pub struct R1CS<F> {
     pub num_inputs: usize,
+    /// Number of public outputs, which come first among the public inputs
+    pub num_outputs: usize,
     pub num_aux: usize,
     pub num_variables: usize,
     pub constraints: Vec<Constraints<F>>,
     pub wire_mapping: Option<Vec<usize>>,
 }
 
+impl<F> R1CS<F> {
+    /// Returns a SHA-256 fingerprint of the shape of the circuit: the number of inputs,
+    /// variables and constraints, and the wires used by each linear combination.
+    /// Coefficients are not included, so that the hash only depends on the structure.
+    pub fn structural_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update((self.num_inputs as u64).to_le_bytes());
+        hasher.update((self.num_variables as u64).to_le_bytes());
+        hasher.update((self.constraints.len() as u64).to_le_bytes());
+        for (a, b, c) in &self.constraints {
+            for lc in [a, b, c] {
+                hasher.update((lc.len() as u64).to_le_bytes());
+                for (index, _) in lc {
+                    hasher.update((*index as u64).to_le_bytes());
+                }
+            }
+        }
+        hasher.finalize().into()
+    }
+}
+
 impl<F: PrimeField> From<R1CSFile<F>> for R1CS<F> {
     fn from(file: R1CSFile<F>) -> Self {
         let num_inputs = (1 + file.header.n_pub_in + file.header.n_pub_out) as usize;
@@ -31,6 +56,7 @@ impl<F: PrimeField> From<R1CSFile<F>> for R1CS<F> {
         R1CS {
             num_aux,
             num_inputs,
+            num_outputs: file.header.n_pub_out as usize,
             num_variables,
             constraints: file.constraints,
             wire_mapping: Some(file.wire_mapping.iter().map(|e| *e as usize).collect()),
@@ -43,6 +69,26 @@ pub struct R1CSFile<F: PrimeField> {
     pub header: Header,
     pub constraints: Vec<Constraints<F>>,
     pub wire_mapping: Vec<u64>,
+    /// Custom gate templates declared by the circuit (section 4), if any
+    pub custom_gates: Option<Vec<CustomGate<F>>>,
+    /// Applications of the custom gates to signals (section 5), if any
+    pub custom_gate_uses: Option<Vec<CustomGateUse>>,
+    unhandled_sections: Vec<u32>,
+}
+
+/// A custom gate template declared with `pragma custom_templates`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomGate<F> {
+    pub template_name: String,
+    pub parameters: Vec<F>,
+}
+
+/// An instance of a custom gate, applied to the listed signals
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomGateUse {
+    /// Index of the gate in [`R1CSFile::custom_gates`]
+    pub id: u32,
+    pub signals: Vec<u64>,
 }
 
 impl<F: PrimeField> R1CSFile<F> {
@@ -51,7 +97,19 @@ impl<F: PrimeField> R1CSFile<F> {
     /// ```rust,ignore
     /// let reader = BufReader::new(Cursor::new(&data[..]));
     /// ```
-    pub fn new<R: Read + Seek>(mut reader: R) -> IoResult<R1CSFile<F>> {
+    pub fn new<R: Read + Seek>(reader: R) -> IoResult<R1CSFile<F>> {
+        Self::read(reader, true)
+    }
+
+    /// Same as [`new`](Self::new), but does not check that the prime of the file matches
+    /// the modulus of `F`. Coefficients are read using the header's field size and reduced
+    /// modulo the modulus of `F`, which is only meaningful if the caller knows the two
+    /// fields to be compatible, e.g. for circuits compiled by forks of circom.
+    pub fn new_unchecked<R: Read + Seek>(reader: R) -> IoResult<R1CSFile<F>> {
+        Self::read(reader, false)
+    }
+
+    fn read<R: Read + Seek>(mut reader: R, strict: bool) -> IoResult<R1CSFile<F>> {
         let mut magic = [0u8; 4];
         reader.read_exact(&mut magic)?;
         if magic != [0x72, 0x31, 0x63, 0x73] {
@@ -89,61 +147,75 @@ impl<F: PrimeField> R1CSFile<F> {
         let header_type = 1;
         let constraint_type = 2;
         let wire2label_type = 3;
-
-        let header_offset = sec_offsets.get(&header_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section offset for header type found",
-            )
-        });
-
-        reader.seek(SeekFrom::Start(*header_offset?))?;
-
-        let header_size = sec_sizes.get(&header_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section size for header type found",
-            )
-        });
-
-        let header = Header::new(&mut reader, *header_size?)?;
-
-        let constraint_offset = sec_offsets.get(&constraint_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section offset for constraint type found",
-            )
-        });
-
-        reader.seek(SeekFrom::Start(*constraint_offset?))?;
-
-        let constraints = read_constraints::<&mut R, F>(&mut reader, &header)?;
-
-        let wire2label_offset = sec_offsets.get(&wire2label_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section offset for wire2label type found",
-            )
-        });
-
-        reader.seek(SeekFrom::Start(*wire2label_offset?))?;
-
-        let wire2label_size = sec_sizes.get(&wire2label_type).ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "No section size for wire2label type found",
-            )
-        });
-
-        let wire_mapping = read_map(&mut reader, *wire2label_size?, &header)?;
+        let custom_gates_list_type = 4;
+        let custom_gates_uses_type = 5;
+
+        let mut unhandled_sections = sec_offsets
+            .keys()
+            .copied()
+            .filter(|sec_type| !(header_type..=custom_gates_uses_type).contains(sec_type))
+            .collect::<Vec<_>>();
+        unhandled_sections.sort_unstable();
+
+        let section = |sec_type: u32, name: &str| -> IoResult<(u64, u64)> {
+            match (sec_offsets.get(&sec_type), sec_sizes.get(&sec_type)) {
+                (Some(offset), Some(size)) => Ok((*offset, *size)),
+                _ => Err(IoError(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("the r1cs has no {name} section (type {sec_type})"),
+                ))),
+            }
+        };
+
+        let (header_offset, header_size) = section(header_type, "header")?;
+        reader.seek(SeekFrom::Start(header_offset))?;
+        let header = Header::new::<F, _>(&mut reader, header_size, strict)?;
+
+        let (constraint_offset, constraint_size) = section(constraint_type, "constraints")?;
+        reader.seek(SeekFrom::Start(constraint_offset))?;
+        let constraints =
+            read_constraints::<&mut R, F>(&mut reader, constraint_size, &header, strict)?;
+
+        let (wire2label_offset, wire2label_size) = section(wire2label_type, "wire-to-label map")?;
+        reader.seek(SeekFrom::Start(wire2label_offset))?;
+        let wire_mapping = read_map(&mut reader, wire2label_size, &header)?;
+
+        let custom_gates = match sec_offsets.get(&custom_gates_list_type) {
+            Some(offset) => {
+                reader.seek(SeekFrom::Start(*offset))?;
+                Some(read_custom_gates_list::<&mut R, F>(
+                    &mut reader,
+                    &header,
+                    strict,
+                )?)
+            }
+            None => None,
+        };
+
+        let custom_gate_uses = match sec_offsets.get(&custom_gates_uses_type) {
+            Some(offset) => {
+                reader.seek(SeekFrom::Start(*offset))?;
+                Some(read_custom_gates_uses(&mut reader)?)
+            }
+            None => None,
+        };
 
         Ok(R1CSFile {
             version,
             header,
             constraints,
             wire_mapping,
+            custom_gates,
+            custom_gate_uses,
+            unhandled_sections,
         })
     }
+
+    /// Returns the types of the sections of the file which were skipped because they are
+    /// not known to this parser, e.g. sections added by a newer version of circom
+    pub fn unhandled_sections(&self) -> Vec<u32> {
+        self.unhandled_sections.clone()
+    }
 }
 
 pub struct Header {
@@ -158,14 +230,14 @@ pub struct Header {
 }
 
 impl Header {
-    fn new<R: Read>(mut reader: R, size: u64) -> IoResult<Header> {
+    /// Returns the prime of the field of the circuit, e.g. to compare it with the one of
+    /// the [`WitnessCalculator`](crate::WitnessCalculator)
+    pub fn prime(&self) -> BigInt {
+        BigInt::from(BigUint::from_bytes_le(&self.prime_size))
+    }
+
+    fn new<F: PrimeField, R: Read>(mut reader: R, size: u64, strict: bool) -> IoResult<Header> {
         let field_size = reader.read_u32::<LittleEndian>()?;
-        if field_size != 32 {
-            return Err(IoError(Error::new(
-                ErrorKind::InvalidData,
-                "This parser only supports 32-byte fields",
-            )));
-        }
 
         if size != 32 + field_size as u64 {
             return Err(IoError(Error::new(
@@ -177,13 +249,11 @@ impl Header {
         let mut prime_size = vec![0u8; field_size as usize];
         reader.read_exact(&mut prime_size)?;
 
-        if prime_size
-            != hex::decode("010000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430")
-                .unwrap()
-        {
+        let modulus: BigUint = F::MODULUS.into();
+        if strict && BigUint::from_bytes_le(&prime_size) != modulus {
             return Err(IoError(Error::new(
                 ErrorKind::InvalidData,
-                "This parser only supports bn256",
+                "Prime does not match the modulus of the provided field",
             )));
         }
 
@@ -200,31 +270,81 @@ impl Header {
     }
 }
 
-fn read_constraint_vec<R: Read, F: PrimeField>(mut reader: R) -> IoResult<ConstraintVec<F>> {
+/// Reads a `field_size`-byte little-endian coefficient. Values outside the field are
+/// rejected in strict mode, and reduced otherwise.
+fn read_field<R: Read, F: PrimeField>(
+    mut reader: R,
+    field_size: usize,
+    strict: bool,
+) -> IoResult<F> {
+    let mut buf = vec![0u8; field_size];
+    reader.read_exact(&mut buf)?;
+    if !strict {
+        return Ok(F::from_le_bytes_mod_order(&buf));
+    }
+    F::BigInt::try_from(BigUint::from_bytes_le(&buf))
+        .ok()
+        .and_then(F::from_bigint)
+        .ok_or_else(|| {
+            IoError(Error::new(
+                ErrorKind::InvalidData,
+                "Coefficient is not a valid field element",
+            ))
+        })
+}
+
+fn read_constraint_vec<R: Read, F: PrimeField>(
+    mut reader: R,
+    header: &Header,
+    strict: bool,
+) -> IoResult<ConstraintVec<F>> {
     let n_vec = reader.read_u32::<LittleEndian>()? as usize;
     let mut vec = Vec::with_capacity(n_vec);
     for _ in 0..n_vec {
         vec.push((
             reader.read_u32::<LittleEndian>()? as usize,
-            F::deserialize_uncompressed(&mut reader)?,
+            read_field::<&mut R, F>(&mut reader, header.field_size as usize, strict)?,
         ));
     }
     Ok(vec)
 }
 
 fn read_constraints<R: Read, F: PrimeField>(
-    mut reader: R,
+    reader: R,
+    size: u64,
     header: &Header,
+    strict: bool,
 ) -> IoResult<Vec<Constraints<F>>> {
-    // todo check section size
-    let mut vec = Vec::with_capacity(header.n_constraints as usize);
+    // bound the reader to the section, so that a wrong constraint count cannot read past it
+    let mut reader = reader.take(size);
+    let truncated = |err| match err {
+        IoError(err) if err.kind() == ErrorKind::UnexpectedEof => IoError(Error::new(
+            ErrorKind::InvalidData,
+            "Constraint section is smaller than the declared number of constraints",
+        )),
+        err => err,
+    };
+
+    // every constraint takes at least 12 bytes, don't trust the header for the allocation
+    let capacity = std::cmp::min(header.n_constraints as u64, size / 12);
+    let mut vec = Vec::with_capacity(capacity as usize);
     for _ in 0..header.n_constraints {
         vec.push((
-            read_constraint_vec::<&mut R, F>(&mut reader)?,
-            read_constraint_vec::<&mut R, F>(&mut reader)?,
-            read_constraint_vec::<&mut R, F>(&mut reader)?,
+            read_constraint_vec::<&mut Take<R>, F>(&mut reader, header, strict)
+                .map_err(truncated)?,
+            read_constraint_vec::<&mut Take<R>, F>(&mut reader, header, strict)
+                .map_err(truncated)?,
+            read_constraint_vec::<&mut Take<R>, F>(&mut reader, header, strict)
+                .map_err(truncated)?,
         ));
     }
+
+    if reader.limit() != 0 {
+        return Err(IoError(Error::new(
+            ErrorKind::InvalidData,
+            "Constraint section is larger than the declared number of constraints",
+        )));
+    }
     Ok(vec)
 }
 
@@ -248,15 +368,62 @@ fn read_map<R: Read>(mut reader: R, size: u64, header: &Header) -> IoResult<Vec<
     Ok(vec)
 }
 
+fn read_custom_gates_list<R: Read, F: PrimeField>(
+    mut reader: R,
+    header: &Header,
+    strict: bool,
+) -> IoResult<Vec<CustomGate<F>>> {
+    let num_gates = reader.read_u32::<LittleEndian>()?;
+    let mut vec = Vec::with_capacity(num_gates as usize);
+    for _ in 0..num_gates {
+        let template_name = read_string(&mut reader)?;
+        let num_parameters = reader.read_u32::<LittleEndian>()?;
+        let parameters = (0..num_parameters)
+            .map(|_| read_field::<&mut R, F>(&mut reader, header.field_size as usize, strict))
+            .collect::<IoResult<_>>()?;
+        vec.push(CustomGate {
+            template_name,
+            parameters,
+        });
+    }
+    Ok(vec)
+}
+
+fn read_custom_gates_uses<R: Read>(mut reader: R) -> IoResult<Vec<CustomGateUse>> {
+    let num_uses = reader.read_u32::<LittleEndian>()?;
+    let mut vec = Vec::with_capacity(num_uses as usize);
+    for _ in 0..num_uses {
+        let id = reader.read_u32::<LittleEndian>()?;
+        let num_signals = reader.read_u32::<LittleEndian>()?;
+        let signals = (0..num_signals)
+            .map(|_| reader.read_u64::<LittleEndian>())
+            .collect::<Result<_, _>>()?;
+        vec.push(CustomGateUse { id, signals });
+    }
+    Ok(vec)
+}
+
+/// Reads a null-terminated string
+fn read_string<R: Read>(mut reader: R) -> IoResult<String> {
+    let mut bytes = Vec::new();
+    loop {
+        match reader.read_u8()? {
+            0 => break,
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes)
+        .map_err(|_| IoError(Error::new(ErrorKind::InvalidData, "Invalid UTF-8 string")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ark_bn254::Fr;
     use ark_std::io::{BufReader, Cursor};
 
-    #[test]
-    fn sample() {
-        let data = hex_literal::hex!(
+    fn sample_data() -> Vec<u8> {
+        hex_literal::hex!(
             "
         72316373
         01000000
@@ -306,7 +473,35 @@ mod tests {
         0f000000 00000000
         44010000 00000000
     "
-        );
+        )
+        .to_vec()
+    }
+
+    #[test]
+    fn structural_hash() {
+        let data = sample_data();
+        let reader = BufReader::new(Cursor::new(&data[..]));
+        let r1cs: R1CS<Fr> = R1CSFile::new(reader).unwrap().into();
+        let hash = r1cs.structural_hash();
+
+        // coefficients do not contribute to the hash
+        let mut other = r1cs.clone();
+        other.constraints[0].0[0].1 = Fr::from(42);
+        assert_eq!(other.structural_hash(), hash);
+
+        // wires do
+        let mut other = r1cs.clone();
+        other.constraints[0].0[0].0 = 4;
+        assert_ne!(other.structural_hash(), hash);
+
+        let mut other = r1cs;
+        other.constraints.pop();
+        assert_ne!(other.structural_hash(), hash);
+    }
+
+    #[test]
+    fn sample() {
+        let data = sample_data();
 
         let reader = BufReader::new(Cursor::new(&data[..]));
         let file = R1CSFile::<Fr>::new(reader).unwrap();
@@ -335,5 +530,281 @@ mod tests {
 
         assert_eq!(file.wire_mapping.len(), 7);
         assert_eq!(file.wire_mapping[1], 3);
+
+        assert!(file.custom_gates.is_none());
+        assert!(file.custom_gate_uses.is_none());
+    }
+
+    #[test]
+    fn prime() {
+        let data = sample_data();
+        let file = R1CSFile::<Fr>::new(Cursor::new(&data[..])).unwrap();
+        let modulus: BigUint = Fr::MODULUS.into();
+        assert_eq!(file.header.prime(), BigInt::from(modulus));
+    }
+
+    #[test]
+    fn sample_bls12_381() {
+        let data = hex_literal::hex!(
+            "
+        72316373
+        01000000
+        03000000
+        01000000 40000000 00000000
+        20000000
+        01000000 ffffffff fe5bfeff 02a4bd53 05d8a109 08d83933 487d9d29 53a7ed73
+        07000000
+        01000000
+        02000000
+        03000000
+        e8030000 00000000
+        03000000
+        02000000 88020000 00000000
+        02000000
+        05000000 03000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        06000000 08000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000
+        00000000 02000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000 14000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000 0C000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000
+        00000000 05000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000 07000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000
+        01000000 04000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        04000000 08000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        05000000 03000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000
+        03000000 2C000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        06000000 06000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        00000000
+        01000000
+        06000000 04000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000
+        00000000 06000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000 0B000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000 05000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        01000000
+        06000000 58020000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000 38000000 00000000
+        00000000 00000000
+        03000000 00000000
+        0a000000 00000000
+        0b000000 00000000
+        0c000000 00000000
+        0f000000 00000000
+        44010000 00000000
+    "
+        );
+
+        let reader = BufReader::new(Cursor::new(&data[..]));
+        let file = R1CSFile::<ark_bls12_381::Fr>::new(reader).unwrap();
+        assert_eq!(file.header.field_size, 32);
+        assert_eq!(file.header.n_wires, 7);
+        assert_eq!(file.header.n_constraints, 3);
+        assert_eq!(file.constraints[0].0[0].1, ark_bls12_381::Fr::from(3));
+        assert_eq!(file.constraints[2].1[0].1, ark_bls12_381::Fr::from(6));
+
+        // the same file must be rejected when read over a different field
+        let reader = BufReader::new(Cursor::new(&data[..]));
+        assert!(R1CSFile::<Fr>::new(reader).is_err());
+
+        // unless the caller explicitly opts out of the check
+        let reader = BufReader::new(Cursor::new(&data[..]));
+        let file = R1CSFile::<Fr>::new_unchecked(reader).unwrap();
+        assert_eq!(file.constraints[0].0[0].1, Fr::from(3));
+        assert_eq!(file.constraints[2].1[0].1, Fr::from(6));
+    }
+
+    #[test]
+    fn sample_48_byte_field() {
+        let data = hex_literal::hex!(
+            "
+        72316373
+        01000000
+        03000000
+        01000000 50000000 00000000
+        30000000
+        abaaffff fffffeb9 ffff53b1 feffab1e 24f6b0f6 a0d23067 bf1285f3 844b7764
+        d7ac4b43 b6a71b4b 9ae67f39 ea11011a
+        07000000
+        01000000
+        02000000
+        03000000
+        e8030000 00000000
+        03000000
+        02000000 98030000 00000000
+        02000000
+        05000000 03000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        06000000 08000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000
+        00000000 02000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000 14000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000 0C000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000
+        00000000 05000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000 07000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000
+        01000000 04000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        04000000 08000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        05000000 03000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000
+        03000000 2C000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        06000000 06000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        00000000
+        01000000
+        06000000 04000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000
+        00000000 06000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000 0B000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000 05000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        01000000
+        06000000 58020000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000 38000000 00000000
+        00000000 00000000
+        03000000 00000000
+        0a000000 00000000
+        0b000000 00000000
+        0c000000 00000000
+        0f000000 00000000
+        44010000 00000000
+    "
+        );
+
+        let reader = BufReader::new(Cursor::new(&data[..]));
+        let file = R1CSFile::<ark_bls12_381::Fq>::new(reader).unwrap();
+        assert_eq!(file.header.field_size, 48);
+        assert_eq!(file.header.prime_size.len(), 48);
+        assert_eq!(file.header.n_wires, 7);
+        assert_eq!(file.header.n_constraints, 3);
+
+        assert_eq!(file.constraints.len(), 3);
+        assert_eq!(file.constraints[0].0[0].1, ark_bls12_381::Fq::from(3));
+        assert_eq!(file.constraints[2].1[0].0, 0);
+        assert_eq!(file.constraints[2].1[0].1, ark_bls12_381::Fq::from(6));
+        assert_eq!(file.constraints[2].2[0].1, ark_bls12_381::Fq::from(600));
+
+        assert_eq!(file.wire_mapping.len(), 7);
+        assert_eq!(file.wire_mapping[1], 3);
+    }
+
+    #[test]
+    fn custom_gates() {
+        let data = hex_literal::hex!(
+            "
+        72316373
+        01000000
+        05000000
+        01000000 40000000 00000000
+        20000000
+        010000f0 93f5e143 9170b979 48e83328 5d588181 b64550b8 29a031e1 724e6430
+        07000000
+        01000000
+        02000000
+        03000000
+        e8030000 00000000
+        03000000
+        02000000 88020000 00000000
+        02000000
+        05000000 03000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        06000000 08000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000
+        00000000 02000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000 14000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000 0C000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000
+        00000000 05000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000 07000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000
+        01000000 04000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        04000000 08000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        05000000 03000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000
+        03000000 2C000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        06000000 06000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        00000000
+        01000000
+        06000000 04000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000
+        00000000 06000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        02000000 0B000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000 05000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        01000000
+        06000000 58020000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        03000000 38000000 00000000
+        00000000 00000000
+        03000000 00000000
+        0a000000 00000000
+        0b000000 00000000
+        0c000000 00000000
+        0f000000 00000000
+        44010000 00000000
+        04000000 2d000000 00000000
+        01000000
+        434d756c 00
+        01000000
+        05000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000
+        05000000 44000000 00000000
+        02000000
+        00000000 03000000
+        01000000 00000000 02000000 00000000 03000000 00000000
+        00000000 03000000
+        04000000 00000000 05000000 00000000 06000000 00000000
+    "
+        );
+
+        let reader = BufReader::new(Cursor::new(&data[..]));
+        let file = R1CSFile::<Fr>::new(reader).unwrap();
+        assert_eq!(file.constraints.len(), 3);
+
+        let gates = file.custom_gates.unwrap();
+        assert_eq!(gates.len(), 1);
+        assert_eq!(gates[0].template_name, "CMul");
+        assert_eq!(gates[0].parameters, vec![Fr::from(5)]);
+
+        let uses = file.custom_gate_uses.unwrap();
+        assert_eq!(uses.len(), 2);
+        assert_eq!(uses[0].id, 0);
+        assert_eq!(uses[0].signals, vec![1, 2, 3]);
+        assert_eq!(uses[1].signals, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn constraint_section_size() {
+        // n_constraints is stored right after the n_labels field of the header
+        let offset = 84;
+        let mut data = sample_data();
+        assert_eq!(data[offset], 3);
+
+        for n_constraints in [2, 4] {
+            data[offset] = n_constraints;
+            let reader = BufReader::new(Cursor::new(&data[..]));
+            match R1CSFile::<Fr>::new(reader) {
+                Err(IoError(err)) => assert_eq!(err.kind(), ErrorKind::InvalidData),
+                _ => panic!("expected an invalid data error"),
+            }
+        }
+    }
+
+    #[test]
+    fn unhandled_sections() {
+        let mut data = sample_data();
+        let file = R1CSFile::<Fr>::new(Cursor::new(&data[..])).unwrap();
+        assert!(file.unhandled_sections().is_empty());
+
+        // append an unknown section of 4 bytes
+        data[8] = 4;
+        data.extend(hex_literal::hex!("09000000 04000000 00000000 01020304"));
+        let file = R1CSFile::<Fr>::new(Cursor::new(&data[..])).unwrap();
+        assert_eq!(file.unhandled_sections(), vec![9]);
+
+        // drop the wire-to-label map, which is the last of the sample's sections
+        let mut data = sample_data();
+        data[8] = 2;
+        data.truncate(data.len() - 12 - 56);
+        let err = R1CSFile::<Fr>::new(Cursor::new(&data[..])).err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("the r1cs has no wire-to-label map section (type 3)"));
     }
 }
@@ -0,0 +1,81 @@
+//! Reader for the `.sym` symbol files produced by circom with `--sym`
+//!
+//! Each line describes a signal as `labelIdx,varIdx,componentIdx,name`, where `varIdx` is
+//! the index of the signal in the witness, or -1 if it was optimized away.
+use color_eyre::{eyre::eyre, Result};
+use std::{fs::File, io::BufRead, io::BufReader, path::Path};
+
+/// A named signal of the circuit
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub label: usize,
+    /// Index of the signal in the witness, if it was not optimized away
+    pub wire: Option<usize>,
+    pub component: usize,
+    /// Fully qualified name of the signal, e.g. `main.out[2]`
+    pub name: String,
+}
+
+/// Reads the symbols of a `.sym` file
+pub fn read_sym(path: impl AsRef<Path>) -> Result<Vec<Symbol>> {
+    read_sym_from(BufReader::new(File::open(path)?))
+}
+
+/// Reads the symbols of a `.sym` file from any buffered reader
+pub fn read_sym_from<R: BufRead>(reader: R) -> Result<Vec<Symbol>> {
+    let mut symbols = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        symbols.push(parse_symbol(&line).ok_or_else(|| eyre!("invalid symbol `{line}`"))?);
+    }
+    Ok(symbols)
+}
+
+fn parse_symbol(line: &str) -> Option<Symbol> {
+    let mut parts = line.splitn(4, ',');
+    let label = parts.next()?.parse().ok()?;
+    let wire = match parts.next()?.parse::<i64>().ok()? {
+        -1 => None,
+        wire => Some(usize::try_from(wire).ok()?),
+    };
+    let component = parts.next()?.parse().ok()?;
+    let name = parts.next()?.to_string();
+    Some(Symbol {
+        label,
+        wire,
+        component,
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mycircuit() {
+        let symbols = read_sym("./test-vectors/mycircuit.sym").unwrap();
+        assert_eq!(symbols.len(), 3);
+        assert_eq!(
+            symbols[2],
+            Symbol {
+                label: 3,
+                wire: Some(1),
+                component: 0,
+                name: "main.c".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn optimized_away() {
+        let symbols = read_sym_from("1,1,0,main.out[0]\n2,-1,1,main.sub.x\n".as_bytes()).unwrap();
+        assert_eq!(symbols[1].wire, None);
+        assert_eq!(symbols[1].name, "main.sub.x");
+
+        assert!(read_sym_from("1,x,0,main.out".as_bytes()).is_err());
+    }
+}
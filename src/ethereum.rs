@@ -1,15 +1,95 @@
 //! Helpers for converting Arkworks types to U256-tuples as expected by the
 //! Solidity Groth16 Verifier smart contracts
+//!
+//! The types are generic over the [`Uint256`] of either ethers, with the `ethereum`
+//! feature, or alloy, with the `ethereum-alloy` feature. They default to the [`U256`] of
+//! ethers, while the [`alloy`] module names them with the one of alloy.
 use ark_ff::{BigInteger, PrimeField};
-use ethers_core::types::U256;
+use color_eyre::{eyre::eyre, Result};
 use num_traits::Zero;
 
 use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
 use ark_serialize::CanonicalDeserialize;
 
-pub struct Inputs(pub Vec<U256>);
+#[cfg(feature = "ethereum-alloy")]
+pub mod alloy;
+#[cfg(feature = "bls12-381")]
+pub mod bls;
+pub mod plonk;
+
+#[cfg(feature = "ethereum")]
+pub use ethers_core::types::U256;
+
+// Without the `ethereum` feature the types have no usable default, rather than defaulting
+// to the U256 of alloy, so that enabling the feature elsewhere in the dependency graph
+// does not change the meaning of code written for alloy
+#[cfg(feature = "ethereum")]
+type DefaultUint = U256;
+#[cfg(not(feature = "ethereum"))]
+type DefaultUint = NoDefaultUint;
+
+/// The default of the type parameters when the `ethereum` feature is disabled. It does not
+/// implement [`Uint256`], so the parameter must be given, e.g. as in [`alloy::Proof`].
+#[cfg(not(feature = "ethereum"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NoDefaultUint {}
+
+// The U256 used where it does not show in the result, e.g. to encode calldata
+#[cfg(feature = "ethereum")]
+pub(crate) type AnyUint = ethers_core::types::U256;
+#[cfg(not(feature = "ethereum"))]
+pub(crate) type AnyUint = alloy_primitives::U256;
+
+/// A 256-bit unsigned integer, as used by the Ethereum libraries
+pub trait Uint256: Copy + Default + Ord + std::fmt::Debug {
+    fn from_be_bytes(bytes: [u8; 32]) -> Self;
+    fn to_be_bytes(&self) -> [u8; 32];
+}
+
+#[cfg(feature = "ethereum")]
+impl Uint256 for ethers_core::types::U256 {
+    fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        Self::from_big_endian(&bytes)
+    }
+
+    fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        self.to_big_endian(&mut bytes);
+        bytes
+    }
+}
+
+#[cfg(feature = "ethereum-alloy")]
+impl Uint256 for alloy_primitives::U256 {
+    fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        Self::from_be_bytes::<32>(bytes)
+    }
+
+    fn to_be_bytes(&self) -> [u8; 32] {
+        self.to_be_bytes::<32>()
+    }
+}
+
+pub struct Inputs<U = DefaultUint>(pub Vec<U>);
+
+impl<U: Uint256> Inputs<U> {
+    /// Decodes the public inputs from calldata in the layout written by [`to_calldata`],
+    /// without the function selector, i.e. the words following the proof. Every input must
+    /// be a scalar field element.
+    pub fn from_calldata(calldata: &[u8]) -> Result<Self> {
+        let inputs = calldata
+            .get(32 * 8..)
+            .filter(|inputs| inputs.len() % 32 == 0)
+            .ok_or_else(|| eyre!("calldata must be a proof followed by 32 byte words"))?;
+        inputs
+            .chunks(32)
+            .map(word_to_u256::<Fr, U>)
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+}
 
-impl From<&[Fr]> for Inputs {
+impl<U: Uint256> From<&[Fr]> for Inputs<U> {
     fn from(src: &[Fr]) -> Self {
         let els = src.iter().map(|point| point_to_u256(*point)).collect();
 
@@ -18,13 +98,13 @@ impl From<&[Fr]> for Inputs {
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct G1 {
-    pub x: U256,
-    pub y: U256,
+pub struct G1<U = DefaultUint> {
+    pub x: U,
+    pub y: U,
 }
 
-impl From<G1> for G1Affine {
-    fn from(src: G1) -> G1Affine {
+impl<U: Uint256> From<G1<U>> for G1Affine {
+    fn from(src: G1<U>) -> G1Affine {
         let x: Fq = u256_to_point(src.x);
         let y: Fq = u256_to_point(src.y);
         if x.is_zero() && y.is_zero() {
@@ -35,15 +115,15 @@ impl From<G1> for G1Affine {
     }
 }
 
-type G1Tup = (U256, U256);
+type G1Tup<U> = (U, U);
 
-impl G1 {
-    pub fn as_tuple(&self) -> (U256, U256) {
+impl<U: Uint256> G1<U> {
+    pub fn as_tuple(&self) -> (U, U) {
         (self.x, self.y)
     }
 }
 
-impl From<&G1Affine> for G1 {
+impl<U: Uint256> From<&G1Affine> for G1<U> {
     fn from(p: &G1Affine) -> Self {
         Self {
             x: point_to_u256(p.x),
@@ -53,13 +133,13 @@ impl From<&G1Affine> for G1 {
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct G2 {
-    pub x: [U256; 2],
-    pub y: [U256; 2],
+pub struct G2<U = DefaultUint> {
+    pub x: [U; 2],
+    pub y: [U; 2],
 }
 
-impl From<G2> for G2Affine {
-    fn from(src: G2) -> G2Affine {
+impl<U: Uint256> From<G2<U>> for G2Affine {
+    fn from(src: G2<U>) -> G2Affine {
         let c0 = u256_to_point(src.x[0]);
         let c1 = u256_to_point(src.x[1]);
         let x = Fq2::new(c0, c1);
@@ -76,16 +156,16 @@ impl From<G2> for G2Affine {
     }
 }
 
-type G2Tup = ([U256; 2], [U256; 2]);
+type G2Tup<U> = ([U; 2], [U; 2]);
 
-impl G2 {
+impl<U: Uint256> G2<U> {
     // NB: Serialize the c1 limb first.
-    pub fn as_tuple(&self) -> G2Tup {
+    pub fn as_tuple(&self) -> G2Tup<U> {
         ([self.x[1], self.x[0]], [self.y[1], self.y[0]])
     }
 }
 
-impl From<&G2Affine> for G2 {
+impl<U: Uint256> From<&G2Affine> for G2<U> {
     fn from(p: &G2Affine) -> Self {
         Self {
             x: [point_to_u256(p.x.c0), point_to_u256(p.x.c1)],
@@ -95,19 +175,64 @@ impl From<&G2Affine> for G2 {
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Proof {
-    pub a: G1,
-    pub b: G2,
-    pub c: G1,
+pub struct Proof<U = DefaultUint> {
+    pub a: G1<U>,
+    pub b: G2<U>,
+    pub c: G1<U>,
 }
 
-impl Proof {
-    pub fn as_tuple(&self) -> (G1Tup, G2Tup, G1Tup) {
+impl<U: Uint256> Proof<U> {
+    pub fn as_tuple(&self) -> (G1Tup<U>, G2Tup<U>, G1Tup<U>) {
         (self.a.as_tuple(), self.b.as_tuple(), self.c.as_tuple())
     }
+
+    /// Decodes the proof from calldata in the layout written by [`to_calldata`], without
+    /// the function selector. Only the first 8 words are read, and every coordinate must
+    /// be a base field element of a point on the curve.
+    pub fn from_calldata(calldata: &[u8]) -> Result<Self> {
+        if calldata.len() < 32 * 8 {
+            return Err(eyre!(
+                "calldata is {} bytes long, a proof needs 256",
+                calldata.len()
+            ));
+        }
+        let words = (0..8)
+            .map(|i| word_to_u256::<Fq, U>(&calldata[32 * i..32 * (i + 1)]))
+            .collect::<Result<Vec<_>>>()?;
+
+        // the c1 limbs of G2 coordinates come first, see `G2::as_tuple`
+        let proof = Self {
+            a: G1 {
+                x: words[0],
+                y: words[1],
+            },
+            b: G2 {
+                x: [words[3], words[2]],
+                y: [words[5], words[4]],
+            },
+            c: G1 {
+                x: words[6],
+                y: words[7],
+            },
+        };
+
+        let on_curve = [proof.a, proof.c].iter().all(|p| {
+            let (x, y): (Fq, Fq) = (u256_to_point(p.x), u256_to_point(p.y));
+            (x.is_zero() && y.is_zero()) || G1Affine::new_unchecked(x, y).is_on_curve()
+        });
+        let b = &proof.b;
+        let x = Fq2::new(u256_to_point(b.x[0]), u256_to_point(b.x[1]));
+        let y = Fq2::new(u256_to_point(b.y[0]), u256_to_point(b.y[1]));
+        let b_on_curve =
+            (x.is_zero() && y.is_zero()) || G2Affine::new_unchecked(x, y).is_on_curve();
+        if !(on_curve && b_on_curve) {
+            return Err(eyre!("proof point is not on the curve"));
+        }
+        Ok(proof)
+    }
 }
 
-impl From<ark_groth16::Proof<Bn254>> for Proof {
+impl<U: Uint256> From<ark_groth16::Proof<Bn254>> for Proof<U> {
     fn from(proof: ark_groth16::Proof<Bn254>) -> Self {
         Self {
             a: G1::from(&proof.a),
@@ -117,8 +242,8 @@ impl From<ark_groth16::Proof<Bn254>> for Proof {
     }
 }
 
-impl From<Proof> for ark_groth16::Proof<Bn254> {
-    fn from(src: Proof) -> ark_groth16::Proof<Bn254> {
+impl<U: Uint256> From<Proof<U>> for ark_groth16::Proof<Bn254> {
+    fn from(src: Proof<U>) -> ark_groth16::Proof<Bn254> {
         ark_groth16::Proof {
             a: src.a.into(),
             b: src.b.into(),
@@ -127,17 +252,30 @@ impl From<Proof> for ark_groth16::Proof<Bn254> {
     }
 }
 
-#[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct VerifyingKey {
-    pub alpha1: G1,
-    pub beta2: G2,
-    pub gamma2: G2,
-    pub delta2: G2,
-    pub ic: Vec<G1>,
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VerifyingKey<U = DefaultUint> {
+    pub alpha1: G1<U>,
+    pub beta2: G2<U>,
+    pub gamma2: G2<U>,
+    pub delta2: G2<U>,
+    pub ic: Vec<G1<U>>,
 }
 
-impl VerifyingKey {
-    pub fn as_tuple(&self) -> (G1Tup, G2Tup, G2Tup, G2Tup, Vec<G1Tup>) {
+impl<U: Uint256> VerifyingKey<U> {
+    /// Parses the `verification_key.json` produced by `snarkjs zkey export verificationkey`
+    pub fn from_snarkjs_json(json: &str) -> color_eyre::Result<Self> {
+        crate::snarkjs::verifying_key_from_json(json).map(Into::into)
+    }
+
+    /// Converts the key to the `verification_key.json` format of snarkjs. The conversion goes
+    /// through the arkworks key, so the G2 limbs are written in the order of snarkjs
+    /// regardless of the order used for Solidity by [`G2::as_tuple`].
+    pub fn to_snarkjs_json(&self) -> serde_json::Value {
+        crate::snarkjs::vk_to_snarkjs_json(&self.clone().into())
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn as_tuple(&self) -> (G1Tup<U>, G2Tup<U>, G2Tup<U>, G2Tup<U>, Vec<G1Tup<U>>) {
         (
             self.alpha1.as_tuple(),
             self.beta2.as_tuple(),
@@ -148,7 +286,7 @@ impl VerifyingKey {
     }
 }
 
-impl From<ark_groth16::VerifyingKey<Bn254>> for VerifyingKey {
+impl<U: Uint256> From<ark_groth16::VerifyingKey<Bn254>> for VerifyingKey<U> {
     fn from(vk: ark_groth16::VerifyingKey<Bn254>) -> Self {
         Self {
             alpha1: G1::from(&vk.alpha_g1),
@@ -160,8 +298,8 @@ impl From<ark_groth16::VerifyingKey<Bn254>> for VerifyingKey {
     }
 }
 
-impl From<VerifyingKey> for ark_groth16::VerifyingKey<Bn254> {
-    fn from(src: VerifyingKey) -> ark_groth16::VerifyingKey<Bn254> {
+impl<U: Uint256> From<VerifyingKey<U>> for ark_groth16::VerifyingKey<Bn254> {
+    fn from(src: VerifyingKey<U>) -> ark_groth16::VerifyingKey<Bn254> {
         ark_groth16::VerifyingKey {
             alpha_g1: src.alpha1.into(),
             beta_g2: src.beta2.into(),
@@ -172,23 +310,255 @@ impl From<VerifyingKey> for ark_groth16::VerifyingKey<Bn254> {
     }
 }
 
+/// ABI-encodes a proof and its public inputs as the arguments of the `verifyProof` function
+/// of the verifier generated by `snarkjs zkey export solidityverifier`, i.e. the values
+/// printed by `snarkjs zkey export soliditycalldata`. The 4 byte function selector is not
+/// included.
+pub fn to_calldata<U: Uint256>(proof: &Proof<U>, inputs: &Inputs<U>) -> Vec<u8> {
+    let (a, b, c) = proof.as_tuple();
+    let words = [a.0, a.1]
+        .into_iter()
+        .chain(b.0)
+        .chain(b.1)
+        .chain([c.0, c.1])
+        .chain(inputs.0.iter().copied());
+
+    let mut calldata = Vec::with_capacity(32 * (8 + inputs.0.len()));
+    for word in words {
+        calldata.extend_from_slice(&word.to_be_bytes());
+    }
+    calldata
+}
+
+/// Reads the number of public inputs of the circuit of a zkey, i.e. the length of the
+/// `uint[N]` array expected by the `verifyProof` function of its verifier
+pub fn num_public_inputs_from_zkey<R: std::io::Read + std::io::Seek>(
+    reader: &mut R,
+) -> Result<usize> {
+    Ok(crate::read_zkey_num_public(reader)?)
+}
+
+/// Generates the source of a Solidity contract verifying Groth16 proofs for `vk`, in the form
+/// of the one exported by `snarkjs zkey export solidityverifier`. The verification key is
+/// inlined as constants, and `verifyProof` takes the arguments encoded by [`to_calldata`].
+pub fn export_solidity_verifier(vk: &ark_groth16::VerifyingKey<Bn254>) -> String {
+    use crate::{g1_coordinates, g2_coordinates};
+    use std::fmt::Write;
+
+    let mut constants = String::new();
+    let (x, y) = g1_coordinates(&vk.alpha_g1);
+    writeln!(constants, "    uint256 constant alphax = {x};").unwrap();
+    writeln!(constants, "    uint256 constant alphay = {y};").unwrap();
+    // the precompile expects the coefficients of the G2 coordinates in reverse order
+    for (name, point) in [
+        ("beta", &vk.beta_g2),
+        ("gamma", &vk.gamma_g2),
+        ("delta", &vk.delta_g2),
+    ] {
+        let (x, y) = g2_coordinates(point);
+        writeln!(constants, "    uint256 constant {name}x1 = {};", x.c1).unwrap();
+        writeln!(constants, "    uint256 constant {name}x2 = {};", x.c0).unwrap();
+        writeln!(constants, "    uint256 constant {name}y1 = {};", y.c1).unwrap();
+        writeln!(constants, "    uint256 constant {name}y2 = {};", y.c0).unwrap();
+    }
+    constants.push('\n');
+    for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+        let (x, y) = g1_coordinates(point);
+        writeln!(constants, "    uint256 constant IC{i}x = {x};").unwrap();
+        writeln!(constants, "    uint256 constant IC{i}y = {y};").unwrap();
+    }
+
+    let num_inputs = vk.gamma_abc_g1.len().saturating_sub(1);
+    let mut vk_x = String::new();
+    let mut check_fields = String::new();
+    for i in 0..num_inputs {
+        writeln!(
+            vk_x,
+            "                g1_mulAccC(_pVk, IC{}x, IC{}y, calldataload(add(pubSignals, {})))",
+            i + 1,
+            i + 1,
+            i * 32
+        )
+        .unwrap();
+        writeln!(
+            check_fields,
+            "            checkField(calldataload(add(_pubSignals, {})))",
+            i * 32
+        )
+        .unwrap();
+    }
+
+    SOLIDITY_VERIFIER
+        .replace("<%constants%>", constants.trim_end())
+        .replace("<%num_inputs%>", &num_inputs.to_string())
+        .replace("<%vk_x%>", vk_x.trim_end())
+        .replace("<%check_fields%>", check_fields.trim_end())
+}
+
+// The pairing check is e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1, with
+// vk_x = IC0 + sum(input_i * IC_i), using the precompiles of EIP-196 and EIP-197
+const SOLIDITY_VERIFIER: &str = r#"// SPDX-License-Identifier: MIT
+// Generated by ark-circom
+pragma solidity >=0.7.0 <0.9.0;
+
+contract Groth16Verifier {
+    // Scalar field size
+    uint256 constant r = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+    // Base field size
+    uint256 constant q = 21888242871839275222246405745257275088696311157297823662689037894645226208583;
+
+    // Verification Key data
+<%constants%>
+
+    // Memory data
+    uint16 constant pVk = 0;
+    uint16 constant pPairing = 128;
+
+    uint16 constant pLastMem = 896;
+
+    function verifyProof(
+        uint256[2] calldata _pA,
+        uint256[2][2] calldata _pB,
+        uint256[2] calldata _pC,
+        uint256[<%num_inputs%>] calldata _pubSignals
+    ) public view returns (bool) {
+        assembly {
+            function checkField(v) {
+                if iszero(lt(v, r)) {
+                    mstore(0, 0)
+                    return(0, 0x20)
+                }
+            }
+
+            // Adds s * (x, y) to the G1 point stored at pR
+            function g1_mulAccC(pR, x, y, s) {
+                let success
+                let mIn := mload(0x40)
+                mstore(mIn, x)
+                mstore(add(mIn, 32), y)
+                mstore(add(mIn, 64), s)
+
+                success := staticcall(sub(gas(), 2000), 7, mIn, 96, mIn, 64)
+
+                if iszero(success) {
+                    mstore(0, 0)
+                    return(0, 0x20)
+                }
+
+                mstore(add(mIn, 64), mload(pR))
+                mstore(add(mIn, 96), mload(add(pR, 32)))
+
+                success := staticcall(sub(gas(), 2000), 6, mIn, 128, pR, 64)
+
+                if iszero(success) {
+                    mstore(0, 0)
+                    return(0, 0x20)
+                }
+            }
+
+            function checkPairing(pA, pB, pC, pubSignals, pMem) -> isOk {
+                let _pPairing := add(pMem, pPairing)
+                let _pVk := add(pMem, pVk)
+
+                mstore(_pVk, IC0x)
+                mstore(add(_pVk, 32), IC0y)
+
+                // Compute the linear combination vk_x
+<%vk_x%>
+
+                // -A
+                mstore(_pPairing, calldataload(pA))
+                mstore(add(_pPairing, 32), mod(sub(q, calldataload(add(pA, 32))), q))
+
+                // B
+                mstore(add(_pPairing, 64), calldataload(pB))
+                mstore(add(_pPairing, 96), calldataload(add(pB, 32)))
+                mstore(add(_pPairing, 128), calldataload(add(pB, 64)))
+                mstore(add(_pPairing, 160), calldataload(add(pB, 96)))
+
+                // alpha1
+                mstore(add(_pPairing, 192), alphax)
+                mstore(add(_pPairing, 224), alphay)
+
+                // beta2
+                mstore(add(_pPairing, 256), betax1)
+                mstore(add(_pPairing, 288), betax2)
+                mstore(add(_pPairing, 320), betay1)
+                mstore(add(_pPairing, 352), betay2)
+
+                // vk_x
+                mstore(add(_pPairing, 384), mload(add(pMem, pVk)))
+                mstore(add(_pPairing, 416), mload(add(pMem, add(pVk, 32))))
+
+                // gamma2
+                mstore(add(_pPairing, 448), gammax1)
+                mstore(add(_pPairing, 480), gammax2)
+                mstore(add(_pPairing, 512), gammay1)
+                mstore(add(_pPairing, 544), gammay2)
+
+                // C
+                mstore(add(_pPairing, 576), calldataload(pC))
+                mstore(add(_pPairing, 608), calldataload(add(pC, 32)))
+
+                // delta2
+                mstore(add(_pPairing, 640), deltax1)
+                mstore(add(_pPairing, 672), deltax2)
+                mstore(add(_pPairing, 704), deltay1)
+                mstore(add(_pPairing, 736), deltay2)
+
+                let success := staticcall(sub(gas(), 2000), 8, _pPairing, 768, _pPairing, 0x20)
+
+                isOk := and(success, mload(_pPairing))
+            }
+
+            let pMem := mload(0x40)
+            mstore(0x40, add(pMem, pLastMem))
+
+            // Validate that all public inputs are in the scalar field
+<%check_fields%>
+
+            // Validate all evaluations
+            let isValid := checkPairing(_pA, _pB, _pC, _pubSignals, pMem)
+
+            mstore(0, isValid)
+            return(0, 0x20)
+        }
+    }
+}
+"#;
+
+// Reads a big endian word, which must be an element of the field F
+fn word_to_u256<F: PrimeField, U: Uint256>(word: &[u8]) -> Result<U> {
+    let value = U::from_be_bytes(word.try_into().expect("words are 32 bytes"));
+    if value >= bigint_to_u256(F::MODULUS) {
+        return Err(eyre!(
+            "0x{} is not a valid field element",
+            hex::encode(word)
+        ));
+    }
+    Ok(value)
+}
+
 // Helper for converting a PrimeField to its U256 representation for Ethereum compatibility
-fn u256_to_point<F: PrimeField>(point: U256) -> F {
-    let mut buf = [0; 32];
-    point.to_little_endian(&mut buf);
+fn u256_to_point<F: PrimeField, U: Uint256>(point: U) -> F {
+    let mut buf = point.to_be_bytes();
+    buf.reverse();
     let bigint = F::BigInt::deserialize_uncompressed(&buf[..]).expect("always works");
     F::from_bigint(bigint).expect("always works")
 }
 
 // Helper for converting a PrimeField to its U256 representation for Ethereum compatibility
 // (U256 reads data as big endian)
-fn point_to_u256<F: PrimeField>(point: F) -> U256 {
-    let point = point.into_bigint();
-    let point_bytes = point.to_bytes_be();
-    U256::from(&point_bytes[..])
+fn point_to_u256<F: PrimeField, U: Uint256>(point: F) -> U {
+    bigint_to_u256(point.into_bigint())
 }
 
-#[cfg(test)]
+fn bigint_to_u256<B: BigInteger, U: Uint256>(bigint: B) -> U {
+    let bytes = bigint.to_bytes_be();
+    U::from_be_bytes(bytes.try_into().expect("256-bit field"))
+}
+
+#[cfg(all(test, feature = "ethereum"))]
 mod tests {
     use super::*;
     use ark_bn254::Fq;
@@ -215,7 +585,7 @@ mod tests {
     #[test]
     fn convert_fq() {
         let el = fq();
-        let el2 = point_to_u256(el);
+        let el2: U256 = point_to_u256(el);
         let el3: Fq = u256_to_point(el2);
         let el4 = point_to_u256(el3);
         assert_eq!(el, el3);
@@ -225,7 +595,7 @@ mod tests {
     #[test]
     fn convert_fr() {
         let el = fr();
-        let el2 = point_to_u256(el);
+        let el2: U256 = point_to_u256(el);
         let el3: Fr = u256_to_point(el2);
         let el4 = point_to_u256(el3);
         assert_eq!(el, el3);
@@ -235,7 +605,7 @@ mod tests {
     #[test]
     fn convert_g1() {
         let el = g1();
-        let el2 = G1::from(&el);
+        let el2: G1 = G1::from(&el);
         let el3: G1Affine = el2.into();
         let el4 = G1::from(&el3);
         assert_eq!(el, el3);
@@ -245,7 +615,7 @@ mod tests {
     #[test]
     fn convert_g2() {
         let el = g2();
-        let el2 = G2::from(&el);
+        let el2: G2 = G2::from(&el);
         let el3: G2Affine = el2.into();
         let el4 = G2::from(&el3);
         assert_eq!(el, el3);
@@ -261,11 +631,112 @@ mod tests {
             delta_g2: g2(),
             gamma_abc_g1: vec![g1(), g1(), g1()],
         };
-        let vk_ethers = VerifyingKey::from(vk.clone());
+        let vk_ethers: VerifyingKey = VerifyingKey::from(vk.clone());
         let ark_vk: ark_groth16::VerifyingKey<Bn254> = vk_ethers.into();
         assert_eq!(ark_vk, vk);
     }
 
+    #[test]
+    fn vk_from_snarkjs_json() {
+        let json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let vk: VerifyingKey = VerifyingKey::from_snarkjs_json(&json).unwrap();
+        assert_eq!(vk.ic.len(), 2);
+
+        let ark_vk: ark_groth16::VerifyingKey<Bn254> = vk.clone().into();
+        assert_eq!(VerifyingKey::from(ark_vk), vk);
+    }
+
+    #[test]
+    fn vk_snarkjs_json_roundtrip() {
+        let json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let vk: VerifyingKey = VerifyingKey::from_snarkjs_json(&json).unwrap();
+
+        let written = vk.to_snarkjs_json();
+        let expected: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(written["vk_beta_2"], expected["vk_beta_2"]);
+        assert_eq!(written["IC"], expected["IC"]);
+
+        let read: VerifyingKey = VerifyingKey::from_snarkjs_json(&written.to_string()).unwrap();
+        assert_eq!(read, vk);
+    }
+
+    #[test]
+    fn calldata() {
+        // output of `snarkjs zkey export soliditycalldata public.json proof.json`
+        const GENERATECALL: &str = r#"["0x0dc94ea9fccfba93e54bfab6f0fb3354c6faa156c2db760902f541b32a64a064","0x1b69fcdc91dc3e98b9f4b3b667a849aae98160a36c913d63d9dcd1ca1e35080f"],[["0x0e16ad41ab02f32980fbc4442a806ad95de4343d42f10d69197a97e6c3fa517d","0x08934f8dd59a3b5e04d9603c7e40fa7150bcc0faad47c9f0ad57aa3379bedc09"],["0x1d136b00a31a52564bcdeafbaf3569a44984f3eae6805ca5db9927917ab01bd9","0x04165c34db638e7b6ef4d57c6d783b95d1f2d34eb9d5aebc56b6f7735c9b24ff"]],["0x2dc56e89744bab3b7beedf6c005949bdd52dcc2c7afae9588ec34e5ad414db1d","0x19293380e8e3f5c1f517f305308217a4febf4c986846bec36c6cb07cd7f13d3c"],["0x2cb82c15880738d566e327fb4cee58930ca32b80bf4ea966a271ca25cdff4319"]"#;
+        let expected = GENERATECALL
+            .split('"')
+            .filter_map(|word| word.strip_prefix("0x"))
+            .flat_map(|word| hex::decode(word).unwrap())
+            .collect::<Vec<_>>();
+
+        let proof = std::fs::read_to_string("./test-vectors/proof.json").unwrap();
+        let proof = crate::snarkjs::from_snarkjs_proof(&serde_json::from_str(&proof).unwrap());
+        let inputs = std::fs::read_to_string("./test-vectors/public.json").unwrap();
+        let inputs =
+            crate::snarkjs::public_inputs_from_json(&serde_json::from_str(&inputs).unwrap());
+
+        let calldata = to_calldata::<U256>(
+            &Proof::from(proof.unwrap()),
+            &Inputs::from(&inputs.unwrap()[..]),
+        );
+        assert_eq!(calldata.len(), 32 * 9);
+        assert_eq!(calldata, expected);
+    }
+
+    #[test]
+    fn calldata_roundtrip() {
+        let proof: Proof = Proof::from(ark_groth16::Proof::<Bn254> {
+            a: g1(),
+            b: g2(),
+            c: g1(),
+        });
+        let inputs = Inputs::from(&[fr(), Fr::from(33)][..]);
+        let mut calldata = to_calldata(&proof, &inputs);
+
+        assert_eq!(Proof::<U256>::from_calldata(&calldata).unwrap(), proof);
+        assert_eq!(
+            Inputs::<U256>::from_calldata(&calldata).unwrap().0,
+            inputs.0
+        );
+
+        // not a multiple of 32 bytes
+        assert!(Inputs::<U256>::from_calldata(&calldata[..calldata.len() - 1]).is_err());
+        // larger than the scalar field modulus
+        calldata[32 * 8..32 * 9].fill(0xff);
+        assert!(Inputs::<U256>::from_calldata(&calldata).is_err());
+        // larger than the base field modulus
+        calldata[..32].fill(0xff);
+        assert!(Proof::<U256>::from_calldata(&calldata).is_err());
+        // not on the curve
+        calldata[..32].fill(0);
+        calldata[31] = 1;
+        assert!(Proof::<U256>::from_calldata(&calldata).is_err());
+        assert!(Proof::<U256>::from_calldata(&calldata[..255]).is_err());
+    }
+
+    #[test]
+    fn num_public_inputs() {
+        let mut file = std::fs::File::open("./test-vectors/test.zkey").unwrap();
+        assert_eq!(num_public_inputs_from_zkey(&mut file).unwrap(), 1);
+    }
+
+    #[test]
+    fn solidity_verifier() {
+        let mut file = std::fs::File::open("./test-vectors/test.zkey").unwrap();
+        let (params, _matrices) = crate::read_zkey(&mut file).unwrap();
+        let source = export_solidity_verifier(&params.vk);
+
+        // constants of test-vectors/verification_key.json
+        assert!(source.contains("uint256 constant alphax = 20491192805390485299153009773594534940189261866228447918068658471970481763042;"));
+        assert!(source.contains("uint256 constant betax1 = 4252822878758300859123897981450591353533073413197771768651442665752259397132;"));
+        assert!(source.contains("uint256 constant betax2 = 6375614351688725206403948262868962793625744043794305715222011528459656738731;"));
+        assert!(source.contains("uint256 constant IC1x"));
+        assert!(!source.contains("IC2x"));
+        assert!(source.contains("uint256[1] calldata _pubSignals"));
+        assert!(!source.contains("<%"));
+    }
+
     #[test]
     fn convert_proof() {
         let p = ark_groth16::Proof::<Bn254> {
@@ -273,7 +744,7 @@ mod tests {
             b: g2(),
             c: g1(),
         };
-        let p2 = Proof::from(p.clone());
+        let p2: Proof = Proof::from(p.clone());
         let p3 = ark_groth16::Proof::from(p2);
         assert_eq!(p, p3);
     }
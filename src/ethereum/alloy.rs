@@ -0,0 +1,50 @@
+//! The types of the [`ethereum`](super) module with the [`U256`] of alloy, which are
+//! available with the `ethereum-alloy` feature whether or not the `ethereum` feature of
+//! ethers is enabled as well
+pub use alloy_primitives::U256;
+
+pub type Inputs = super::Inputs<U256>;
+pub type G1 = super::G1<U256>;
+pub type G2 = super::G2<U256>;
+pub type Proof = super::Proof<U256>;
+pub type VerifyingKey = super::VerifyingKey<U256>;
+pub type PlonkProof = super::plonk::PlonkProof<U256>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::{point_to_u256, to_calldata};
+    use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+    use ark_std::UniformRand;
+
+    #[test]
+    fn proof_roundtrip() {
+        let rng = &mut ark_std::test_rng();
+        let proof = ark_groth16::Proof::<Bn254> {
+            a: G1Affine::rand(rng),
+            b: G2Affine::rand(rng),
+            c: G1Affine::rand(rng),
+        };
+        let converted = Proof::from(proof.clone());
+        assert_eq!(converted.a.x, point_to_u256::<_, U256>(proof.a.x));
+
+        let inputs = Inputs::from(&[Fr::from(33)][..]);
+        let calldata = to_calldata(&converted, &inputs);
+        assert_eq!(Proof::from_calldata(&calldata).unwrap(), converted);
+        assert_eq!(
+            Inputs::from_calldata(&calldata).unwrap().0,
+            [U256::from(33)]
+        );
+        assert_eq!(ark_groth16::Proof::<Bn254>::from(converted), proof);
+    }
+
+    #[test]
+    fn vk_from_snarkjs_json() {
+        let json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let vk = VerifyingKey::from_snarkjs_json(&json).unwrap();
+        assert_eq!(vk.ic.len(), 2);
+
+        let ark_vk: ark_groth16::VerifyingKey<Bn254> = vk.clone().into();
+        assert_eq!(VerifyingKey::from(ark_vk), vk);
+    }
+}
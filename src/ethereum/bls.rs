@@ -0,0 +1,67 @@
+//! Encoding of BLS12-381 points as expected by the precompiles of EIP-2537, for verifying
+//! BLS12-381 Groth16 proofs on-chain
+//!
+//! Each base field element is written as 64 big-endian bytes, i.e. its 48 bytes padded
+//! with 16 leading zeros, and an element of Fq2 as `c0` followed by `c1`. The point at
+//! infinity is encoded as all zeros.
+use ark_bls12_381::{Fq, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField};
+
+/// Encodes a G1 point as the 128 bytes of its `x` and `y` coordinates
+pub fn g1_to_eip2537(point: &G1Affine) -> [u8; 128] {
+    let mut bytes = [0u8; 128];
+    if let Some((x, y)) = point.xy() {
+        write_fq(&mut bytes[..64], x);
+        write_fq(&mut bytes[64..], y);
+    }
+    bytes
+}
+
+/// Encodes a G2 point as the 256 bytes of its `x` and `y` coordinates
+pub fn g2_to_eip2537(point: &G2Affine) -> [u8; 256] {
+    let mut bytes = [0u8; 256];
+    if let Some((x, y)) = point.xy() {
+        write_fq(&mut bytes[..64], &x.c0);
+        write_fq(&mut bytes[64..128], &x.c1);
+        write_fq(&mut bytes[128..192], &y.c0);
+        write_fq(&mut bytes[192..], &y.c1);
+    }
+    bytes
+}
+
+// writes the 48 big-endian bytes of `value` to the end of the 64 bytes of `out`
+fn write_fq(out: &mut [u8], value: &Fq) {
+    let be = value.into_bigint().to_bytes_be();
+    out[64 - be.len()..].copy_from_slice(&be);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generators() {
+        // the generators as encoded in the test vectors of EIP-2537
+        let g1 = hex_literal::hex!(
+            "
+            0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb
+            0000000000000000000000000000000008b3f481e3aaa0f1a09e30ed741d8ae4fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1
+            "
+        );
+        assert_eq!(g1_to_eip2537(&G1Affine::generator()), g1);
+
+        let g2 = hex_literal::hex!(
+            "
+            00000000000000000000000000000000024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8
+            0000000000000000000000000000000013e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e
+            000000000000000000000000000000000ce5d527727d6e118cc9cdc6da2e351aadfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801
+            000000000000000000000000000000000606c4a02ea734cc32acd2b02bc28b99cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be
+            "
+        );
+        assert_eq!(g2_to_eip2537(&G2Affine::generator()), g2);
+
+        assert_eq!(g1_to_eip2537(&G1Affine::identity()), [0u8; 128]);
+        assert_eq!(g2_to_eip2537(&G2Affine::identity()), [0u8; 256]);
+    }
+}
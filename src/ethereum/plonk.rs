@@ -0,0 +1,147 @@
+//! Helpers for converting PLONK proofs of snarkjs to U256-tuples as expected by the
+//! Solidity PLONK verifier exported by `snarkjs zkey export solidityverifier`
+use ark_bn254::Fr;
+use color_eyre::Result;
+use serde_json::Value;
+
+use super::{point_to_u256, DefaultUint, Inputs, Uint256, G1};
+use crate::snarkjs::{fr_from_json, g1_from_json, get};
+
+/// A PLONK proof, made of the commitments to the wire, permutation and quotient
+/// polynomials, the opening proofs and the evaluations at the challenge point
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PlonkProof<U = DefaultUint> {
+    pub a: G1<U>,
+    pub b: G1<U>,
+    pub c: G1<U>,
+    pub z: G1<U>,
+    pub t1: G1<U>,
+    pub t2: G1<U>,
+    pub t3: G1<U>,
+    pub wxi: G1<U>,
+    pub wxiw: G1<U>,
+    pub eval_a: U,
+    pub eval_b: U,
+    pub eval_c: U,
+    pub eval_s1: U,
+    pub eval_s2: U,
+    pub eval_zw: U,
+}
+
+impl<U: Uint256> PlonkProof<U> {
+    /// Parses the `proof.json` produced by `snarkjs plonk prove`
+    pub fn from_snarkjs_json(json: &str) -> Result<Self> {
+        let json: Value = serde_json::from_str(json)?;
+        let g1 = |key| -> Result<G1<U>> { Ok(G1::from(&g1_from_json(get(&json, key)?)?)) };
+        let fr = |key| -> Result<U> { Ok(point_to_u256::<Fr, U>(fr_from_json(get(&json, key)?)?)) };
+
+        Ok(Self {
+            a: g1("A")?,
+            b: g1("B")?,
+            c: g1("C")?,
+            z: g1("Z")?,
+            t1: g1("T1")?,
+            t2: g1("T2")?,
+            t3: g1("T3")?,
+            wxi: g1("Wxi")?,
+            wxiw: g1("Wxiw")?,
+            eval_a: fr("eval_a")?,
+            eval_b: fr("eval_b")?,
+            eval_c: fr("eval_c")?,
+            eval_s1: fr("eval_s1")?,
+            eval_s2: fr("eval_s2")?,
+            eval_zw: fr("eval_zw")?,
+        })
+    }
+
+    /// Returns the proof as the `uint256[24]` taken by the verifier, i.e. the coordinates
+    /// of the commitments followed by the evaluations
+    pub fn as_array(&self) -> [U; 24] {
+        let points = [
+            self.a, self.b, self.c, self.z, self.t1, self.t2, self.t3, self.wxi, self.wxiw,
+        ];
+        let evals = [
+            self.eval_a,
+            self.eval_b,
+            self.eval_c,
+            self.eval_s1,
+            self.eval_s2,
+            self.eval_zw,
+        ];
+
+        let mut words = [U::default(); 24];
+        let coords = points.iter().flat_map(|p| [p.x, p.y]);
+        for (word, value) in words.iter_mut().zip(coords.chain(evals)) {
+            *word = value;
+        }
+        words
+    }
+}
+
+/// ABI-encodes a PLONK proof and its public inputs as the arguments of the `verifyProof`
+/// function of the PLONK verifier generated by snarkjs, i.e. the values printed by
+/// `snarkjs zkey export soliditycalldata`. The 4 byte function selector is not included.
+pub fn to_calldata<U: Uint256>(proof: &PlonkProof<U>, inputs: &Inputs<U>) -> Vec<u8> {
+    let words = proof.as_array().into_iter().chain(inputs.0.iter().copied());
+
+    let mut calldata = Vec::with_capacity(32 * (24 + inputs.0.len()));
+    for word in words {
+        calldata.extend_from_slice(&word.to_be_bytes());
+    }
+    calldata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // the encoding is the same with ethers and alloy
+    use crate::ethereum::AnyUint as U256;
+    use serde_json::json;
+
+    #[test]
+    fn calldata() {
+        // the generator, and twice the generator
+        let g = json!(["1", "2", "1"]);
+        let g2 = json!([
+            "1368015179489954701390400359078579693043519447331113978918064868415326638035",
+            "9918110051302171585080402603319702774565515993150576347155970296011118125764",
+            "1"
+        ]);
+        let json = json!({
+            "A": g, "B": g2, "C": g, "Z": g2, "T1": g, "T2": g2, "T3": g, "Wxi": g2, "Wxiw": g,
+            "eval_a": "1", "eval_b": "2", "eval_c": "3", "eval_s1": "4", "eval_s2": "5",
+            "eval_zw": "6",
+            "protocol": "plonk",
+            "curve": "bn128"
+        });
+        let proof = PlonkProof::<U256>::from_snarkjs_json(&json.to_string()).unwrap();
+        let word = |n: u64| point_to_u256::<Fr, U256>(Fr::from(n));
+        assert_eq!(
+            proof.a,
+            G1 {
+                x: word(1),
+                y: word(2)
+            }
+        );
+        assert_eq!(proof.eval_zw, word(6));
+
+        let inputs = Inputs::<U256>::from(&[Fr::from(33)][..]);
+        let calldata = to_calldata(&proof, &inputs);
+        assert_eq!(calldata.len(), 32 * 25);
+
+        let words: Vec<U256> = calldata
+            .chunks(32)
+            .map(|chunk| Uint256::from_be_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(&words[..2], &[word(1), word(2)]);
+        assert_eq!(words[2], proof.b.x);
+        assert_eq!(words[17], proof.wxiw.y);
+        assert_eq!(&words[18..24], &[1, 2, 3, 4, 5, 6].map(word)[..]);
+        assert_eq!(words[24], word(33));
+
+        // points must be on the curve
+        let mut json = json;
+        json["Z"] = json!(["1", "3", "1"]);
+        assert!(PlonkProof::<U256>::from_snarkjs_json(&json.to_string()).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+//! Downloading of circuit artifacts served over HTTP(S)
+use color_eyre::Result;
+
+/// Downloads the file at `url` into memory, failing on non-success status codes
+pub(crate) async fn fetch(url: &str) -> Result<Vec<u8>> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
@@ -1,14 +1,67 @@
 //! Arkworks - Circom Compatibility layer
 //!
 //! Provides bindings to Circom's R1CS, for Groth16 Proof and Witness generation in Rust.
+//!
+//! The wasm witness generators are run with wasmer by default. Without the default `wasmer`
+//! feature, Circom 2 generators can still be run by the wasmi interpreter with the `wasmi`
+//! feature, see [`calculate_witness_with`], but the APIs taking a [`WitnessCalculator`],
+//! e.g. [`CircomBuilder`], are not available.
 mod witness;
-pub use witness::{Wasm, WitnessCalculator};
+#[cfg(feature = "native")]
+pub use witness::NativeWitnessCalculator;
+#[cfg(all(feature = "wasmer", feature = "circom-2"))]
+pub use witness::WasmerRuntime;
+#[cfg(feature = "wasmi")]
+pub use witness::WasmiRuntime;
+#[cfg(feature = "circom-2")]
+pub use witness::{calculate_witness_with, CircomRuntime};
+pub use witness::{read_wtns, write_wtns, WitnessError};
+#[cfg(feature = "wasmer")]
+pub use witness::{PooledWitnessCalculator, Wasm, WitnessCalculator, WitnessCalculatorPool};
 
 pub mod circom;
-pub use circom::{CircomBuilder, CircomCircuit, CircomConfig, CircomReduction};
+#[cfg(feature = "wasmer")]
+pub use circom::{CircomBuilder, CircomConfig};
+pub use circom::{CircomCircuit, CircomReduction, LibsnarkReduction};
 
-#[cfg(feature = "ethereum")]
+#[cfg(any(feature = "ethereum", feature = "ethereum-alloy"))]
 pub mod ethereum;
 
 mod zkey;
-pub use zkey::read_zkey;
+pub use zkey::{
+    check_zkey_matches_r1cs, read_ic_points, read_prepared_vk, read_zkey, read_zkey_ceremony_info,
+    read_zkey_checked, read_zkey_num_constraints, read_zkey_num_public, read_zkey_vk,
+    validate_proving_key_parallel, write_zkey, BinFile, CeremonyContribution, CeremonyInfo,
+    InvalidPoint,
+};
+#[cfg(feature = "parallel")]
+#[doc(hidden)]
+pub use zkey::{deserialize_g1_points_parallel, deserialize_g2_points_parallel};
+#[doc(hidden)]
+pub use zkey::{deserialize_g1_points_sequential, deserialize_g2_points_sequential};
+#[cfg(feature = "wasmer")]
+pub use zkey::{generate_parameters_from_seed, setup_and_write_zkey};
+
+mod prover;
+#[cfg(test)]
+pub(crate) use prover::tests::mycircuit_proof;
+pub use prover::{
+    g1_coordinates, g2_coordinates, groth16_prove, proof_coordinates, write_proof, ProofFormat,
+};
+#[cfg(feature = "wasmer")]
+pub use prover::{prepare_groth16_inputs, prove_with_zkey};
+
+mod verifier;
+pub use verifier::{
+    prepare_vk, read_written_prepared_vk, verify_detailed, verify_with_zkey, write_prepared_vk,
+    VerifyOutcome,
+};
+
+pub mod snarkjs;
+
+#[cfg(feature = "fetch")]
+mod fetch;
+#[cfg(feature = "fetch")]
+pub use zkey::read_zkey_from_url;
+#[cfg(feature = "mmap")]
+pub use zkey::read_zkey_mmap;
@@ -0,0 +1,319 @@
+//! Helpers for creating Groth16 proofs from the matrices of a circuit, e.g. as read from a
+//! zkey, and for taking them apart
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::AffineRepr;
+use ark_ff::Zero;
+use ark_groth16::{Groth16, Proof, ProvingKey};
+use ark_relations::r1cs::ConstraintMatrices;
+use ark_serialize::CanonicalSerialize;
+use ark_std::{
+    rand::{CryptoRng, RngCore},
+    UniformRand,
+};
+use color_eyre::Result;
+use std::io::Write;
+
+use crate::CircomReduction;
+#[cfg(feature = "wasmer")]
+use crate::{read_zkey, WitnessCalculator};
+#[cfg(feature = "wasmer")]
+use color_eyre::eyre::eyre;
+#[cfg(feature = "wasmer")]
+use num_bigint::BigInt;
+#[cfg(feature = "wasmer")]
+use std::io::{Read, Seek};
+#[cfg(feature = "wasmer")]
+use wasmer::Store;
+
+/// Creates a Groth16 proof for the full assignment of the circuit's variables, as returned
+/// by [`WitnessCalculator::calculate_witness_element`](crate::WitnessCalculator::calculate_witness_element).
+/// The number of inputs and constraints are taken from `matrices`, and the blinding factors
+/// are sampled from `rng`.
+pub fn groth16_prove<R: RngCore + CryptoRng>(
+    pk: &ProvingKey<Bn254>,
+    matrices: &ConstraintMatrices<Fr>,
+    full_assignment: &[Fr],
+    rng: &mut R,
+) -> Result<Proof<Bn254>> {
+    let r = Fr::rand(rng);
+    let s = Fr::rand(rng);
+    let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+        pk,
+        r,
+        s,
+        matrices,
+        matrices.num_instance_variables,
+        matrices.num_constraints,
+        full_assignment,
+    )?;
+    Ok(proof)
+}
+
+/// Reads a zkey and calculates the witness for `inputs`, returning the proving key, the
+/// matrices, the full assignment, the number of inputs and the number of constraints, as
+/// expected by `Groth16::create_proof_with_reduction_and_matrices`. Fails if the witness
+/// does not match the variables of the zkey, e.g. because it is for another circuit.
+#[cfg(feature = "wasmer")]
+#[allow(clippy::type_complexity)]
+pub fn prepare_groth16_inputs<R, I>(
+    zkey: &mut R,
+    wtns: &mut WitnessCalculator,
+    store: &mut Store,
+    inputs: I,
+) -> Result<(
+    ProvingKey<Bn254>,
+    ConstraintMatrices<Fr>,
+    Vec<Fr>,
+    usize,
+    usize,
+)>
+where
+    R: Read + Seek,
+    I: IntoIterator<Item = (String, Vec<BigInt>)>,
+{
+    let (pk, matrices) = read_zkey(zkey)?;
+    let full_assignment = wtns.calculate_witness_element::<Fr, _>(store, inputs, false)?;
+
+    // the zkey's matrices count the constant 1 both as an instance and a witness variable
+    let num_variables = matrices.num_instance_variables + matrices.num_witness_variables - 1;
+    if full_assignment.len() != num_variables {
+        return Err(eyre!(
+            "the witness has {} variables, but the zkey expects {num_variables}",
+            full_assignment.len()
+        ));
+    }
+    // the first variable is always the constant 1
+    if full_assignment.first() != Some(&Fr::from(1)) {
+        return Err(eyre!("the witness does not start with the constant 1"));
+    }
+
+    let num_inputs = matrices.num_instance_variables;
+    let num_constraints = matrices.num_constraints;
+    Ok((pk, matrices, full_assignment, num_inputs, num_constraints))
+}
+
+/// Proves a circuit end to end from its zkey and its wasm witness generator, returning the
+/// proof along with the public inputs to verify it against. This is a shortcut for
+/// [`prepare_groth16_inputs`] followed by [`groth16_prove`].
+#[cfg(feature = "wasmer")]
+pub fn prove_with_zkey<R, I, G>(
+    zkey: &mut R,
+    wasm: impl AsRef<std::path::Path>,
+    inputs: I,
+    rng: &mut G,
+) -> Result<(Proof<Bn254>, Vec<Fr>)>
+where
+    R: Read + Seek,
+    I: IntoIterator<Item = (String, Vec<BigInt>)>,
+    G: RngCore + CryptoRng,
+{
+    let mut store = Store::default();
+    let mut wtns = WitnessCalculator::new(&mut store, wasm)?;
+    let (pk, matrices, full_assignment, num_inputs, _) =
+        prepare_groth16_inputs(zkey, &mut wtns, &mut store, inputs)?;
+    let proof = groth16_prove(&pk, &matrices, &full_assignment, rng)?;
+    Ok((proof, full_assignment[1..num_inputs].to_vec()))
+}
+
+/// Returns the affine `(x, y)` coordinates of a G1 point, with the point at infinity
+/// encoded as `(0, 0)` like snarkjs and the Solidity verifier do
+pub fn g1_coordinates(point: &G1Affine) -> (Fq, Fq) {
+    point
+        .xy()
+        .map(|(x, y)| (*x, *y))
+        .unwrap_or((Fq::zero(), Fq::zero()))
+}
+
+/// Returns the affine `(x, y)` coordinates of a G2 point, each as `c0 + c1 * u`, with
+/// the point at infinity encoded as `(0, 0)`
+pub fn g2_coordinates(point: &G2Affine) -> (Fq2, Fq2) {
+    point
+        .xy()
+        .map(|(x, y)| (*x, *y))
+        .unwrap_or((Fq2::zero(), Fq2::zero()))
+}
+
+/// Returns the coordinates of the `(A, B, C)` elements of a proof, e.g. to feed them to
+/// an in-circuit verifier
+#[allow(clippy::type_complexity)]
+pub fn proof_coordinates(proof: &Proof<Bn254>) -> ((Fq, Fq), (Fq2, Fq2), (Fq, Fq)) {
+    (
+        g1_coordinates(&proof.a),
+        g2_coordinates(&proof.b),
+        g1_coordinates(&proof.c),
+    )
+}
+
+/// The formats in which [`write_proof`] can write a proof
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofFormat {
+    /// The compressed arkworks serialization of the proof
+    ArkCanonical,
+    /// The `proof.json` format written by `snarkjs groth16 prove`
+    SnarkjsJson,
+    /// The ABI-encoded proof as written by [`to_calldata`](crate::ethereum::to_calldata),
+    /// without the public inputs
+    #[cfg(any(feature = "ethereum", feature = "ethereum-alloy"))]
+    EthereumCalldata,
+}
+
+/// Writes a proof to `writer` in the given format
+pub fn write_proof<W: Write>(
+    proof: &Proof<Bn254>,
+    format: ProofFormat,
+    writer: &mut W,
+) -> Result<()> {
+    match format {
+        ProofFormat::ArkCanonical => proof.serialize_compressed(writer)?,
+        ProofFormat::SnarkjsJson => {
+            serde_json::to_writer(writer, &crate::snarkjs::to_snarkjs_proof(proof))?
+        }
+        #[cfg(any(feature = "ethereum", feature = "ethereum-alloy"))]
+        ProofFormat::EthereumCalldata => {
+            use crate::ethereum;
+            let proof = ethereum::Proof::<ethereum::AnyUint>::from(proof.clone());
+            writer.write_all(&ethereum::to_calldata(&proof, &ethereum::Inputs(vec![])))?
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::read_zkey;
+    use ark_crypto_primitives::snark::SNARK;
+    use ark_std::rand::thread_rng;
+    use std::fs::File;
+
+    // Proves mycircuit for a = 3, b = 11 with the key of test-vectors/test.zkey, returning
+    // the key, the matrices, the witness and the proof, whose public input is 33
+    pub(crate) fn mycircuit_proof() -> (
+        ProvingKey<Bn254>,
+        ConstraintMatrices<Fr>,
+        Vec<Fr>,
+        Proof<Bn254>,
+    ) {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let (params, matrices) = read_zkey(&mut file).unwrap();
+
+        let full_assignment = [1, 33, 3, 11].map(Fr::from).to_vec();
+        let proof = groth16_prove(&params, &matrices, &full_assignment, &mut thread_rng()).unwrap();
+        (params, matrices, full_assignment, proof)
+    }
+
+    #[test]
+    fn prove() {
+        let (params, _, _, proof) = mycircuit_proof();
+
+        let pvk = Groth16::<Bn254>::process_vk(&params.vk).unwrap();
+        assert!(Groth16::<Bn254>::verify_with_processed_vk(&pvk, &[Fr::from(33)], &proof).unwrap());
+        assert!(
+            !Groth16::<Bn254>::verify_with_processed_vk(&pvk, &[Fr::from(34)], &proof).unwrap()
+        );
+    }
+
+    #[cfg(feature = "wasmer")]
+    #[tokio::test]
+    async fn prepare_inputs() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let mut store = Store::default();
+        let mut wtns = WitnessCalculator::new(&mut store, "./test-vectors/mycircuit.wasm").unwrap();
+        let inputs = [
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ];
+        let (params, matrices, full_assignment, num_inputs, num_constraints) =
+            prepare_groth16_inputs(&mut file, &mut wtns, &mut store, inputs).unwrap();
+
+        let rng = &mut thread_rng();
+        let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+            &params,
+            Fr::rand(rng),
+            Fr::rand(rng),
+            &matrices,
+            num_inputs,
+            num_constraints,
+            &full_assignment,
+        )
+        .unwrap();
+
+        let pvk = Groth16::<Bn254>::process_vk(&params.vk).unwrap();
+        let public_inputs = &full_assignment[1..num_inputs];
+        assert!(Groth16::<Bn254>::verify_with_processed_vk(&pvk, public_inputs, &proof).unwrap());
+    }
+
+    #[cfg(feature = "wasmer")]
+    #[tokio::test]
+    async fn prove_zkey() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let inputs = [
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ];
+        let (proof, public_inputs) = prove_with_zkey(
+            &mut file,
+            "./test-vectors/mycircuit.wasm",
+            inputs,
+            &mut thread_rng(),
+        )
+        .unwrap();
+        assert_eq!(public_inputs, [Fr::from(33)]);
+
+        file.rewind().unwrap();
+        let (params, _) = read_zkey(&mut file).unwrap();
+        let pvk = Groth16::<Bn254>::process_vk(&params.vk).unwrap();
+        assert!(Groth16::<Bn254>::verify_with_processed_vk(&pvk, &public_inputs, &proof).unwrap());
+    }
+
+    #[test]
+    fn write_formats() {
+        use ark_serialize::CanonicalDeserialize;
+
+        let proof = Proof {
+            a: G1Affine::generator(),
+            b: G2Affine::generator(),
+            c: G1Affine::identity(),
+        };
+
+        let mut buf = Vec::new();
+        write_proof(&proof, ProofFormat::ArkCanonical, &mut buf).unwrap();
+        assert_eq!(Proof::deserialize_compressed(&buf[..]).unwrap(), proof);
+
+        let mut buf = Vec::new();
+        write_proof(&proof, ProofFormat::SnarkjsJson, &mut buf).unwrap();
+        let json = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(crate::snarkjs::from_snarkjs_proof(&json).unwrap(), proof);
+
+        #[cfg(any(feature = "ethereum", feature = "ethereum-alloy"))]
+        {
+            let mut buf = Vec::new();
+            write_proof(&proof, ProofFormat::EthereumCalldata, &mut buf).unwrap();
+            assert_eq!(buf.len(), 32 * 8);
+            let decoded =
+                crate::ethereum::Proof::<crate::ethereum::AnyUint>::from_calldata(&buf).unwrap();
+            assert_eq!(Proof::from(decoded), proof);
+        }
+    }
+
+    #[test]
+    fn coordinates() {
+        let g1 = G1Affine::generator();
+        assert_eq!(g1_coordinates(&g1), (Fq::from(1), Fq::from(2)));
+        assert_eq!(
+            g1_coordinates(&G1Affine::identity()),
+            (Fq::zero(), Fq::zero())
+        );
+
+        let g2 = G2Affine::generator();
+        let proof = Proof {
+            a: g1,
+            b: g2,
+            c: G1Affine::identity(),
+        };
+        let (a, b, c) = proof_coordinates(&proof);
+        assert_eq!(a, (Fq::from(1), Fq::from(2)));
+        assert_eq!(G2Affine::new(b.0, b.1), g2);
+        assert_eq!(c, (Fq::zero(), Fq::zero()));
+    }
+}
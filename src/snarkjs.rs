@@ -0,0 +1,269 @@
+//! Helpers for reading and writing the JSON artifacts of snarkjs
+//!
+//! Points are encoded as arrays of decimal strings holding their projective coordinates,
+//! e.g. `["x", "y", "1"]` for G1 and `[["x0", "x1"], ["y0", "y1"], ["1", "0"]]` for G2.
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{
+    pairing::Pairing,
+    short_weierstrass::{Affine, SWCurveConfig},
+    AffineRepr, CurveGroup,
+};
+use ark_ff::{BigInteger256, PrimeField};
+use ark_groth16::{Proof, VerifyingKey};
+use color_eyre::{eyre::eyre, Result};
+use num_bigint::BigUint;
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// Parses the `verification_key.json` produced by `snarkjs zkey export verificationkey`
+/// into an Arkworks VerifyingKey.
+pub fn verifying_key_from_json(json: &str) -> Result<VerifyingKey<Bn254>> {
+    let json: Value = serde_json::from_str(json)?;
+    check_protocol(&json)?;
+
+    let ic = get(&json, "IC")?
+        .as_array()
+        .ok_or_else(|| eyre!("`IC` must be an array of G1 points"))?
+        .iter()
+        .map(g1_from_json)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(VerifyingKey {
+        alpha_g1: g1_from_json(get(&json, "vk_alpha_1")?)?,
+        beta_g2: g2_from_json(get(&json, "vk_beta_2")?)?,
+        gamma_g2: g2_from_json(get(&json, "vk_gamma_2")?)?,
+        delta_g2: g2_from_json(get(&json, "vk_delta_2")?)?,
+        gamma_abc_g1: ic,
+    })
+}
+
+/// Converts a verifying key to the `verification_key.json` format written by
+/// `snarkjs zkey export verificationkey`, including the precomputed `vk_alphabeta_12`.
+pub fn vk_to_snarkjs_json(vk: &VerifyingKey<Bn254>) -> Value {
+    let alphabeta = Bn254::pairing(vk.alpha_g1, vk.beta_g2).0;
+    let fq6_to_json = |value: &ark_bn254::Fq6| {
+        json!([
+            fq2_to_json(&value.c0),
+            fq2_to_json(&value.c1),
+            fq2_to_json(&value.c2)
+        ])
+    };
+
+    json!({
+        "protocol": "groth16",
+        "curve": "bn128",
+        "nPublic": vk.gamma_abc_g1.len().saturating_sub(1),
+        "vk_alpha_1": g1_to_json(&vk.alpha_g1),
+        "vk_beta_2": g2_to_json(&vk.beta_g2),
+        "vk_gamma_2": g2_to_json(&vk.gamma_g2),
+        "vk_delta_2": g2_to_json(&vk.delta_g2),
+        "vk_alphabeta_12": [fq6_to_json(&alphabeta.c0), fq6_to_json(&alphabeta.c1)],
+        "IC": vk.gamma_abc_g1.iter().map(g1_to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Converts a proof to the `proof.json` format written by `snarkjs groth16 prove`, so that
+/// it can be verified with snarkjs and compatible tools.
+pub fn to_snarkjs_proof(proof: &Proof<Bn254>) -> Value {
+    json!({
+        "pi_a": g1_to_json(&proof.a),
+        "pi_b": g2_to_json(&proof.b),
+        "pi_c": g1_to_json(&proof.c),
+        "protocol": "groth16",
+        "curve": "bn128",
+    })
+}
+
+/// Parses a proof in the `proof.json` format written by `snarkjs groth16 prove`.
+pub fn from_snarkjs_proof(json: &Value) -> Result<Proof<Bn254>> {
+    check_protocol(json)?;
+    Ok(Proof {
+        a: g1_from_json(get(json, "pi_a")?)?,
+        b: g2_from_json(get(json, "pi_b")?)?,
+        c: g1_from_json(get(json, "pi_c")?)?,
+    })
+}
+
+/// Converts public inputs to the `public.json` format written by snarkjs, i.e. an array of
+/// decimal strings.
+pub fn public_inputs_to_json(inputs: &[Fr]) -> Value {
+    Value::Array(
+        inputs
+            .iter()
+            .map(|x| Value::String(x.to_string()))
+            .collect(),
+    )
+}
+
+/// Parses public inputs in the `public.json` format written by snarkjs. Values must be
+/// decimal strings of canonical field elements, i.e. less than the modulus.
+pub fn public_inputs_from_json(json: &Value) -> Result<Vec<Fr>> {
+    json.as_array()
+        .ok_or_else(|| eyre!("expected an array of public inputs, got {json}"))?
+        .iter()
+        .map(fr_from_json)
+        .collect()
+}
+
+fn check_protocol(json: &Value) -> Result<()> {
+    match json.get("protocol").and_then(Value::as_str) {
+        None | Some("groth16") => Ok(()),
+        Some(protocol) => Err(eyre!("unsupported protocol `{protocol}`, expected groth16")),
+    }
+}
+
+pub(crate) fn get<'a>(json: &'a Value, key: &str) -> Result<&'a Value> {
+    json.get(key).ok_or_else(|| eyre!("missing key `{key}`"))
+}
+
+fn coords(json: &Value, len: usize) -> Result<&[Value]> {
+    match json.as_array() {
+        Some(coords) if coords.len() == len => Ok(coords),
+        _ => Err(eyre!("expected an array of {len} coordinates, got {json}")),
+    }
+}
+
+fn fq_from_json(json: &Value) -> Result<Fq> {
+    let s = json
+        .as_str()
+        .ok_or_else(|| eyre!("expected a decimal string, got {json}"))?;
+    Fq::from_str(s).map_err(|_| eyre!("invalid base field element `{s}`"))
+}
+
+pub(crate) fn fr_from_json(json: &Value) -> Result<Fr> {
+    let s = json
+        .as_str()
+        .ok_or_else(|| eyre!("expected a decimal string, got {json}"))?;
+    BigUint::from_str(s)
+        .ok()
+        .and_then(|n| BigInteger256::try_from(n).ok())
+        .and_then(Fr::from_bigint)
+        .ok_or_else(|| eyre!("invalid scalar field element `{s}`"))
+}
+
+fn fq2_from_json(json: &Value) -> Result<Fq2> {
+    let c = coords(json, 2)?;
+    Ok(Fq2::new(fq_from_json(&c[0])?, fq_from_json(&c[1])?))
+}
+
+pub(crate) fn g1_from_json(json: &Value) -> Result<G1Affine> {
+    let c = coords(json, 3)?;
+    let point = G1Projective::new_unchecked(
+        fq_from_json(&c[0])?,
+        fq_from_json(&c[1])?,
+        fq_from_json(&c[2])?,
+    )
+    .into_affine();
+    check_point(&point)?;
+    Ok(point)
+}
+
+fn g2_from_json(json: &Value) -> Result<G2Affine> {
+    let c = coords(json, 3)?;
+    let point = G2Projective::new_unchecked(
+        fq2_from_json(&c[0])?,
+        fq2_from_json(&c[1])?,
+        fq2_from_json(&c[2])?,
+    )
+    .into_affine();
+    check_point(&point)?;
+    Ok(point)
+}
+
+// snarkjs writes the point at infinity as (0, 1, 0)
+fn g1_to_json(point: &G1Affine) -> Value {
+    match point.xy() {
+        Some((x, y)) => json!([x.to_string(), y.to_string(), "1"]),
+        None => json!(["0", "1", "0"]),
+    }
+}
+
+fn fq2_to_json(value: &Fq2) -> Value {
+    json!([value.c0.to_string(), value.c1.to_string()])
+}
+
+fn g2_to_json(point: &G2Affine) -> Value {
+    match point.xy() {
+        Some((x, y)) => json!([fq2_to_json(x), fq2_to_json(y), ["1", "0"]]),
+        None => json!([["0", "0"], ["1", "0"], ["0", "0"]]),
+    }
+}
+
+fn check_point<P: SWCurveConfig>(point: &Affine<P>) -> Result<()> {
+    if point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve() {
+        Ok(())
+    } else {
+        Err(eyre!(
+            "point is not in the prime order subgroup of the curve"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_zkey;
+    use std::fs::File;
+
+    #[test]
+    fn verifying_key_matches_zkey() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let (params, _matrices) = read_zkey(&mut file).unwrap();
+
+        let json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let vk = verifying_key_from_json(&json).unwrap();
+        assert_eq!(vk, params.vk);
+    }
+
+    #[test]
+    fn proof_roundtrip() {
+        let json = std::fs::read_to_string("./test-vectors/proof.json").unwrap();
+        let json: Value = serde_json::from_str(&json).unwrap();
+        let proof = from_snarkjs_proof(&json).unwrap();
+        assert_eq!(to_snarkjs_proof(&proof), json);
+
+        let identity = Proof {
+            a: G1Affine::identity(),
+            b: G2Affine::identity(),
+            c: proof.c,
+        };
+        let roundtrip = from_snarkjs_proof(&to_snarkjs_proof(&identity)).unwrap();
+        assert_eq!(roundtrip, identity);
+    }
+
+    #[test]
+    fn public_inputs_roundtrip() {
+        let json = std::fs::read_to_string("./test-vectors/public.json").unwrap();
+        let json: Value = serde_json::from_str(&json).unwrap();
+        let inputs = public_inputs_from_json(&json).unwrap();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(public_inputs_to_json(&inputs), json);
+
+        let modulus = Fr::MODULUS.to_string();
+        assert!(public_inputs_from_json(&serde_json::json!([modulus])).is_err());
+        assert!(public_inputs_from_json(&serde_json::json!(["-1"])).is_err());
+        assert!(public_inputs_from_json(&serde_json::json!([33])).is_err());
+    }
+
+    #[test]
+    fn verifying_key_to_json() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let (params, _matrices) = read_zkey(&mut file).unwrap();
+
+        let json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(vk_to_snarkjs_json(&params.vk), json);
+        assert_eq!(
+            verifying_key_from_json(&vk_to_snarkjs_json(&params.vk).to_string()).unwrap(),
+            params.vk
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_points() {
+        let json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let mut json: Value = serde_json::from_str(&json).unwrap();
+        json["vk_alpha_1"][0] = Value::String("1".to_string());
+        assert!(verifying_key_from_json(&json.to_string()).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Helpers for verifying Groth16 proofs and diagnosing why they are rejected
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{prepare_verifying_key, Groth16, PreparedVerifyingKey, Proof, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use color_eyre::Result;
+use std::io::{Read, Seek, Write};
+
+use crate::read_prepared_vk;
+
+/// The outcome of verifying a proof with [`verify_detailed`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    /// The proof is valid for the public inputs
+    Valid,
+    /// The proof is well formed, but the pairing check failed, e.g. because the public
+    /// inputs or the verifying key do not match the ones it was created for
+    InvalidPairing,
+    /// A point of the proof is not on the curve or not in the prime order subgroup
+    MalformedProof,
+    /// The number of public inputs does not match the verifying key
+    PublicInputCountMismatch { expected: usize, actual: usize },
+}
+
+impl VerifyOutcome {
+    pub fn is_valid(&self) -> bool {
+        *self == Self::Valid
+    }
+}
+
+/// Verifies a proof like `Groth16::verify_with_processed_vk`, but reports why it was
+/// rejected instead of only returning `false`
+pub fn verify_detailed(
+    pvk: &PreparedVerifyingKey<Bn254>,
+    public_inputs: &[Fr],
+    proof: &Proof<Bn254>,
+) -> VerifyOutcome {
+    let expected = pvk.vk.gamma_abc_g1.len().saturating_sub(1);
+    if public_inputs.len() != expected {
+        return VerifyOutcome::PublicInputCountMismatch {
+            expected,
+            actual: public_inputs.len(),
+        };
+    }
+
+    let well_formed = [proof.a, proof.c]
+        .iter()
+        .all(|p| p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve())
+        && proof.b.is_on_curve()
+        && proof.b.is_in_correct_subgroup_assuming_on_curve();
+    if !well_formed {
+        return VerifyOutcome::MalformedProof;
+    }
+
+    match Groth16::<Bn254>::verify_proof(pvk, proof, public_inputs) {
+        Ok(true) => VerifyOutcome::Valid,
+        _ => VerifyOutcome::InvalidPairing,
+    }
+}
+
+/// Verifies a proof against the verifying key of a SnarkJS ZKey file. Only the header and
+/// the IC points are read from the zkey, see [`read_prepared_vk`], so this is much cheaper
+/// than reading the whole proving key.
+pub fn verify_with_zkey<R: Read + Seek>(
+    zkey: &mut R,
+    public_inputs: &[Fr],
+    proof: &Proof<Bn254>,
+) -> Result<bool> {
+    let pvk = read_prepared_vk(zkey)?;
+    Ok(Groth16::<Bn254>::verify_proof(&pvk, proof, public_inputs)?)
+}
+
+/// Prepares a verifying key for verification, precomputing the `alpha * beta` pairing and
+/// the G2 points. Services verifying against a fixed key should do this once, and can cache
+/// the result across restarts with [`write_prepared_vk`].
+pub fn prepare_vk(vk: &VerifyingKey<Bn254>) -> PreparedVerifyingKey<Bn254> {
+    prepare_verifying_key(vk)
+}
+
+/// Writes a prepared verifying key, including its precomputations, so that it can be read
+/// back with [`read_written_prepared_vk`]
+pub fn write_prepared_vk<W: Write>(
+    pvk: &PreparedVerifyingKey<Bn254>,
+    writer: &mut W,
+) -> Result<(), SerializationError> {
+    pvk.serialize_uncompressed(writer)
+}
+
+/// Reads a prepared verifying key written by [`write_prepared_vk`], checking that its
+/// points are valid. Unlike [`read_prepared_vk`](crate::read_prepared_vk), this does not read
+/// a zkey but the precomputed pairing and G2 points of a prepared key.
+pub fn read_written_prepared_vk<R: Read>(
+    reader: &mut R,
+) -> Result<PreparedVerifyingKey<Bn254>, SerializationError> {
+    PreparedVerifyingKey::deserialize_uncompressed(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mycircuit_proof;
+    use ark_bn254::{Fq, G1Affine};
+
+    #[test]
+    fn outcomes() {
+        let (params, _, _, proof) = mycircuit_proof();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        assert_eq!(
+            verify_detailed(&pvk, &[Fr::from(33)], &proof),
+            VerifyOutcome::Valid
+        );
+        assert_eq!(
+            verify_detailed(&pvk, &[Fr::from(34)], &proof),
+            VerifyOutcome::InvalidPairing
+        );
+        assert_eq!(
+            verify_detailed(&pvk, &[Fr::from(33), Fr::from(1)], &proof),
+            VerifyOutcome::PublicInputCountMismatch {
+                expected: 1,
+                actual: 2
+            }
+        );
+
+        let mut malformed = proof;
+        malformed.a = G1Affine::new_unchecked(Fq::from(1), Fq::from(1));
+        assert_eq!(
+            verify_detailed(&pvk, &[Fr::from(33)], &malformed),
+            VerifyOutcome::MalformedProof
+        );
+    }
+
+    #[test]
+    fn cached_pvk() {
+        let (params, _, _, proof) = mycircuit_proof();
+
+        let mut cache = Vec::new();
+        write_prepared_vk(&prepare_vk(&params.vk), &mut cache).unwrap();
+        let pvk = read_written_prepared_vk(&mut &cache[..]).unwrap();
+        assert_eq!(pvk.vk, params.vk);
+        assert!(verify_detailed(&pvk, &[Fr::from(33)], &proof).is_valid());
+
+        assert!(read_written_prepared_vk(&mut &cache[..cache.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn verify_zkey() {
+        let (_, _, _, proof) = mycircuit_proof();
+        let mut data = std::fs::read("./test-vectors/test.zkey").unwrap();
+
+        // overwrite every section after the IC points, which verifying must not read
+        let mut position = 12;
+        while position < data.len() {
+            let id = u32::from_le_bytes(data[position..position + 4].try_into().unwrap());
+            let size = u64::from_le_bytes(data[position + 4..position + 12].try_into().unwrap());
+            let start = position + 12;
+            position = start + size as usize;
+            if id > 3 {
+                data[start..position].fill(0xff);
+            }
+        }
+
+        let mut zkey = std::io::Cursor::new(&data);
+        assert!(verify_with_zkey(&mut zkey, &[Fr::from(33)], &proof).unwrap());
+        zkey.rewind().unwrap();
+        assert!(!verify_with_zkey(&mut zkey, &[Fr::from(34)], &proof).unwrap());
+    }
+}
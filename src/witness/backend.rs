@@ -0,0 +1,525 @@
+//! Witness generation independent of the wasm engine running the circuit
+//!
+//! [`WitnessCalculator`](crate::WitnessCalculator) runs the witness generators with
+//! wasmer, which compiles them to native code. The [`CircomRuntime`] trait captures the
+//! functions exported by the witness generators of Circom 2, so that they can also be run
+//! by other engines, e.g. the [`WasmiRuntime`] interpreter with the `wasmi` feature, on
+//! targets where wasmer is not available. Builds without the default `wasmer` feature only
+//! have the latter.
+use color_eyre::{eyre::eyre, Result};
+use num_bigint::BigInt;
+use num_traits::Zero;
+
+use super::{
+    error::{CircomError, SIGNAL_NOT_FOUND},
+    fnv, WitnessError,
+};
+#[cfg(feature = "wasmer")]
+use super::{Circom2, CircomBase, Wasm};
+#[cfg(feature = "wasmer")]
+use wasmer::Store;
+
+/// The functions exported by the wasm witness generators of Circom 2
+pub trait CircomRuntime {
+    fn init(&mut self, sanity_check: bool) -> Result<()>;
+    fn get_field_num_len32(&mut self) -> Result<u32>;
+    fn get_raw_prime(&mut self) -> Result<()>;
+    fn read_shared_rw_memory(&mut self, i: u32) -> Result<u32>;
+    fn write_shared_rw_memory(&mut self, i: u32, v: u32) -> Result<()>;
+    fn set_input_signal(&mut self, hmsb: u32, hlsb: u32, pos: u32) -> Result<()>;
+    fn get_witness(&mut self, i: u32) -> Result<()>;
+    fn get_witness_size(&mut self) -> Result<u32>;
+    /// Returns the number of values of an input signal, or a negative number if there is no
+    /// such signal. `None` if the wasm was compiled by a circom version without this export.
+    fn get_input_signal_size(&mut self, hmsb: u32, hlsb: u32) -> Result<Option<i32>>;
+    /// Returns the total number of input values, or `None` if the wasm was compiled by a
+    /// circom version without this export.
+    fn get_input_size(&mut self) -> Result<Option<u32>>;
+}
+
+/// Calculates the witness of a Circom 2 circuit with any [`CircomRuntime`], like
+/// [`WitnessCalculator::calculate_witness`](super::WitnessCalculator::calculate_witness)
+/// does with wasmer
+pub fn calculate_witness_with<R, I>(
+    runtime: &mut R,
+    inputs: I,
+    sanity_check: bool,
+) -> Result<Vec<BigInt>>
+where
+    R: CircomRuntime + ?Sized,
+    I: IntoIterator<Item = (String, Vec<BigInt>)>,
+{
+    runtime.init(sanity_check)?;
+    let prime = read_prime_circom2(runtime)?;
+    let witness_size = runtime.get_witness_size()?;
+    let n32 = set_inputs_circom2(runtime, &prime, inputs)?;
+
+    let mut witness = Vec::with_capacity(witness_size as usize);
+    read_witness_circom2(runtime, n32, 0..witness_size, &mut |_, value| {
+        witness.push(value);
+        Ok(())
+    })?;
+    Ok(witness)
+}
+
+fn is_signal_not_found(err: &color_eyre::Report) -> bool {
+    let circom_error = err.downcast_ref::<CircomError>();
+    // wasmer wraps the errors raised by the callbacks of the runtime
+    #[cfg(feature = "wasmer")]
+    let circom_error = circom_error.or_else(|| {
+        err.downcast_ref::<wasmer::RuntimeError>()
+            .and_then(|err| err.downcast_ref::<CircomError>())
+    });
+    circom_error.is_some_and(|err| err.code == SIGNAL_NOT_FOUND)
+}
+
+// Converts big-endian 32-bit limbs, `n32` of them as reported by the wasm, to a BigInt
+fn from_array32(arr: Vec<u32>) -> BigInt {
+    let mut res = BigInt::zero();
+    let radix = BigInt::from(0x100000000u64);
+    for &val in arr.iter() {
+        res = res * &radix + BigInt::from(val);
+    }
+    res
+}
+
+// The inverse of `from_array32`, failing if the value does not fit in `size` limbs
+fn to_array32(s: &BigInt, size: usize) -> Result<Vec<u32>> {
+    let (sign, digits) = s.to_u32_digits();
+    if sign == num_bigint::Sign::Minus {
+        return Err(eyre!("the value {s} is negative"));
+    }
+    if digits.len() > size {
+        return Err(eyre!("the value {s} does not fit in {size} 32-bit limbs"));
+    }
+
+    let mut res = vec![0; size];
+    for (limb, digit) in res.iter_mut().rev().zip(digits) {
+        *limb = digit;
+    }
+    Ok(res)
+}
+
+// Writes the inputs to a Circom 2 runtime, returning the number of 32-bit limbs of the field
+pub(super) fn set_inputs_circom2<R, I>(runtime: &mut R, prime: &BigInt, inputs: I) -> Result<u32>
+where
+    R: CircomRuntime + ?Sized,
+    I: IntoIterator<Item = (String, Vec<BigInt>)>,
+{
+    let n32 = runtime.get_field_num_len32()?;
+
+    // allocate the inputs
+    let mut num_values = 0;
+    for (name, values) in inputs.into_iter() {
+        let (msb, lsb) = fnv(&name);
+
+        // older versions of circom only report unknown signals once they are set
+        if let Some(size) = runtime.get_input_signal_size(msb, lsb)? {
+            let expected =
+                usize::try_from(size).map_err(|_| WitnessError::UnknownInput(name.clone()))?;
+            if values.len() != expected {
+                return Err(WitnessError::InputLength {
+                    name,
+                    expected,
+                    actual: values.len(),
+                }
+                .into());
+            }
+        }
+        num_values += values.len();
+
+        for (i, value) in values.into_iter().enumerate() {
+            // negative values stand for their field element, e.g. -1 for p - 1
+            let value = if value.sign() == num_bigint::Sign::Minus {
+                num::Integer::mod_floor(&value, prime)
+            } else {
+                value
+            };
+            let f_arr = to_array32(&value, n32 as usize)?;
+            for j in 0..n32 {
+                runtime.write_shared_rw_memory(j, f_arr[(n32 as usize) - 1 - (j as usize)])?;
+            }
+            runtime
+                .set_input_signal(msb, lsb, i as u32)
+                .map_err(|err| {
+                    if is_signal_not_found(&err) {
+                        err.wrap_err(WitnessError::UnknownInput(name.clone()))
+                    } else {
+                        err
+                    }
+                })?;
+        }
+    }
+
+    if let Some(expected) = runtime.get_input_size()? {
+        if num_values != expected as usize {
+            return Err(WitnessError::InputCount {
+                expected: expected as usize,
+                actual: num_values,
+            }
+            .into());
+        }
+    }
+
+    Ok(n32)
+}
+
+// Reads the witness elements at `indices` out of a Circom 2 runtime whose inputs are set
+pub(super) fn read_witness_circom2<R: CircomRuntime + ?Sized>(
+    runtime: &mut R,
+    n32: u32,
+    indices: impl Iterator<Item = u32>,
+    sink: &mut dyn FnMut(usize, BigInt) -> Result<()>,
+) -> Result<()> {
+    for i in indices {
+        runtime.get_witness(i)?;
+        let mut arr = vec![0; n32 as usize];
+        for j in 0..n32 {
+            arr[(n32 as usize) - 1 - (j as usize)] = runtime.read_shared_rw_memory(j)?;
+        }
+        sink(i as usize, from_array32(arr))?;
+    }
+    Ok(())
+}
+
+// Reads the prime of a Circom 2 runtime
+pub(super) fn read_prime_circom2<R: CircomRuntime + ?Sized>(runtime: &mut R) -> Result<BigInt> {
+    let n32 = runtime.get_field_num_len32()?;
+    runtime.get_raw_prime()?;
+    let mut arr = vec![0; n32 as usize];
+    for i in 0..n32 {
+        arr[(n32 as usize) - (i as usize) - 1] = runtime.read_shared_rw_memory(i)?;
+    }
+    Ok(from_array32(arr))
+}
+
+// Reads the witness elements at `indices` straight into field elements, without going
+// through BigInt. Circom 2 only ever returns canonical, non-negative values.
+#[cfg(feature = "wasmer")]
+pub(super) fn read_witness_elements_circom2<F: ark_ff::PrimeField, R: CircomRuntime + ?Sized>(
+    runtime: &mut R,
+    n32: u32,
+    indices: std::ops::Range<u32>,
+) -> Result<Vec<F>> {
+    let mut w = Vec::with_capacity(indices.len());
+    let mut bytes = vec![0u8; 4 * n32 as usize];
+    for i in indices {
+        runtime.get_witness(i)?;
+        for j in 0..n32 as usize {
+            let limb = runtime.read_shared_rw_memory(j as u32)?;
+            bytes[4 * j..4 * j + 4].copy_from_slice(&limb.to_le_bytes());
+        }
+
+        let mut repr = F::BigInt::default();
+        let limbs = repr.as_mut();
+        let element = if bytes.len() <= 8 * limbs.len() {
+            for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+                let mut buf = [0u8; 8];
+                buf[..chunk.len()].copy_from_slice(chunk);
+                *limb = u64::from_le_bytes(buf);
+            }
+            F::from_bigint(repr)
+        } else {
+            None
+        };
+        // values larger than the modulus of F are reduced, like `F::from(BigUint)` does
+        w.push(element.unwrap_or_else(|| F::from_le_bytes_mod_order(&bytes)));
+    }
+
+    Ok(w)
+}
+
+/// A wasmer instance of a witness generator, along with its store
+#[cfg(feature = "wasmer")]
+#[derive(Debug)]
+pub struct WasmerRuntime<'a> {
+    wasm: &'a Wasm,
+    store: &'a mut Store,
+}
+
+#[cfg(feature = "wasmer")]
+impl<'a> WasmerRuntime<'a> {
+    pub fn new(wasm: &'a Wasm, store: &'a mut Store) -> Self {
+        Self { wasm, store }
+    }
+}
+
+#[cfg(feature = "wasmer")]
+impl CircomRuntime for WasmerRuntime<'_> {
+    fn init(&mut self, sanity_check: bool) -> Result<()> {
+        self.wasm.init(self.store, sanity_check)
+    }
+
+    fn get_field_num_len32(&mut self) -> Result<u32> {
+        self.wasm.get_field_num_len32(self.store)
+    }
+
+    fn get_raw_prime(&mut self) -> Result<()> {
+        self.wasm.get_raw_prime(self.store)
+    }
+
+    fn read_shared_rw_memory(&mut self, i: u32) -> Result<u32> {
+        self.wasm.read_shared_rw_memory(self.store, i)
+    }
+
+    fn write_shared_rw_memory(&mut self, i: u32, v: u32) -> Result<()> {
+        self.wasm.write_shared_rw_memory(self.store, i, v)
+    }
+
+    fn set_input_signal(&mut self, hmsb: u32, hlsb: u32, pos: u32) -> Result<()> {
+        self.wasm.set_input_signal(self.store, hmsb, hlsb, pos)
+    }
+
+    fn get_witness(&mut self, i: u32) -> Result<()> {
+        self.wasm.get_witness(self.store, i)
+    }
+
+    fn get_witness_size(&mut self) -> Result<u32> {
+        self.wasm.get_witness_size(self.store)
+    }
+
+    fn get_input_signal_size(&mut self, hmsb: u32, hlsb: u32) -> Result<Option<i32>> {
+        self.wasm.get_input_signal_size(self.store, hmsb, hlsb)
+    }
+
+    fn get_input_size(&mut self) -> Result<Option<u32>> {
+        self.wasm.get_input_size(self.store)
+    }
+}
+
+#[cfg(feature = "wasmi")]
+pub use interpreter::WasmiRuntime;
+
+#[cfg(feature = "wasmi")]
+mod interpreter {
+    use super::*;
+    use wasmi::{Caller, Engine, Instance, Linker, Memory, MemoryType, Module, WasmParams};
+
+    impl wasmi::core::HostError for CircomError {}
+
+    // messages printed by the circuit before raising an exception
+    #[derive(Debug, Default)]
+    struct Env {
+        error_message: String,
+    }
+
+    /// A witness generator of Circom 2 run by the wasmi interpreter, which is slower than
+    /// wasmer but does not need to compile native code
+    #[derive(Debug)]
+    pub struct WasmiRuntime {
+        store: wasmi::Store<Env>,
+        instance: Instance,
+    }
+
+    impl WasmiRuntime {
+        /// Instantiates the witness generator from the bytes of its wasm file, with the same
+        /// 2000 pages of memory as a `WitnessCalculator` by default
+        pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self> {
+            let engine = Engine::default();
+            let module = Module::new(&engine, bytes.as_ref())?;
+            let mut store = wasmi::Store::new(&engine, Env::default());
+
+            let pages = super::super::DEFAULT_MEMORY_PAGES;
+            let memory = MemoryType::new(pages, None)
+                .and_then(|ty| Memory::new(&mut store, ty))
+                .map_err(|err| eyre!("cannot allocate the memory of the runtime: {err}"))?;
+            let mut linker = Linker::new(&engine);
+            linker.define("env", "memory", memory)?;
+            linker.func_wrap(
+                "runtime",
+                "exceptionHandler",
+                |mut caller: Caller<'_, Env>, code: i32| -> Result<(), wasmi::Error> {
+                    let message = std::mem::take(&mut caller.data_mut().error_message);
+                    Err(wasmi::Error::host(CircomError::exception(code, &message)))
+                },
+            )?;
+            linker.func_wrap(
+                "runtime",
+                "printErrorMessage",
+                |mut caller: Caller<'_, Env>| -> Result<(), wasmi::Error> {
+                    let message = read_message(&mut caller)?;
+                    let error_message = &mut caller.data_mut().error_message;
+                    error_message.push_str(&message);
+                    error_message.push('\n');
+                    Ok(())
+                },
+            )?;
+            // `log()` output is not reported by this runtime
+            linker.func_wrap("runtime", "writeBufferMessage", || {})?;
+            linker.func_wrap("runtime", "showSharedRWMemory", || {})?;
+            linker.func_wrap("runtime", "printDebug", |_: i32| {})?;
+
+            let instance = linker.instantiate(&mut store, &module)?.start(&mut store)?;
+            Ok(Self { store, instance })
+        }
+
+        /// Same as [`from_bytes`](Self::from_bytes), but reads the wasm file at `path`
+        pub fn new(path: impl AsRef<std::path::Path>) -> Result<Self> {
+            Self::from_bytes(std::fs::read(path)?)
+        }
+
+        fn call<P: WasmParams, R: wasmi::WasmResults>(
+            &mut self,
+            name: &str,
+            params: P,
+        ) -> Result<R> {
+            let func = self
+                .instance
+                .get_typed_func::<P, R>(&self.store, name)
+                .map_err(|err| eyre!("cannot call `{name}`: {err}"))?;
+            func.call(&mut self.store, params).map_err(into_report)
+        }
+
+        fn call_optional<P: WasmParams, R: wasmi::WasmResults>(
+            &mut self,
+            name: &str,
+            params: P,
+        ) -> Result<Option<R>> {
+            match self.instance.get_func(&self.store, name) {
+                Some(_) => self.call(name, params).map(Some),
+                None => Ok(None),
+            }
+        }
+    }
+
+    // keeps the error of the circuit, so that it can be recognized like with wasmer
+    fn into_report(err: wasmi::Error) -> color_eyre::Report {
+        match err.downcast_ref::<CircomError>() {
+            Some(err) => err.clone().into(),
+            None => eyre!("{err}"),
+        }
+    }
+
+    // Reads the current message of the runtime, one character at a time
+    fn read_message(caller: &mut Caller<'_, Env>) -> Result<String, wasmi::Error> {
+        let func = caller
+            .get_export("getMessageChar")
+            .and_then(|export| export.into_func())
+            .ok_or_else(|| wasmi::Error::new("missing export `getMessageChar`"))?
+            .typed::<(), i32>(&*caller)?;
+        let mut bytes = Vec::new();
+        loop {
+            match func.call(&mut *caller, ())? {
+                0 => break,
+                c => bytes.push(c as u8),
+            }
+        }
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    impl CircomRuntime for WasmiRuntime {
+        fn init(&mut self, sanity_check: bool) -> Result<()> {
+            self.call("init", sanity_check as i32)
+        }
+
+        fn get_field_num_len32(&mut self) -> Result<u32> {
+            Ok(self.call::<_, i32>("getFieldNumLen32", ())? as u32)
+        }
+
+        fn get_raw_prime(&mut self) -> Result<()> {
+            self.call("getRawPrime", ())
+        }
+
+        fn read_shared_rw_memory(&mut self, i: u32) -> Result<u32> {
+            Ok(self.call::<_, i32>("readSharedRWMemory", i as i32)? as u32)
+        }
+
+        fn write_shared_rw_memory(&mut self, i: u32, v: u32) -> Result<()> {
+            self.call("writeSharedRWMemory", (i as i32, v as i32))
+        }
+
+        fn set_input_signal(&mut self, hmsb: u32, hlsb: u32, pos: u32) -> Result<()> {
+            self.call("setInputSignal", (hmsb as i32, hlsb as i32, pos as i32))
+        }
+
+        fn get_witness(&mut self, i: u32) -> Result<()> {
+            self.call("getWitness", i as i32)
+        }
+
+        fn get_witness_size(&mut self) -> Result<u32> {
+            Ok(self.call::<_, i32>("getWitnessSize", ())? as u32)
+        }
+
+        fn get_input_signal_size(&mut self, hmsb: u32, hlsb: u32) -> Result<Option<i32>> {
+            self.call_optional("getInputSignalSize", (hmsb as i32, hlsb as i32))
+        }
+
+        fn get_input_size(&mut self) -> Result<Option<u32>> {
+            Ok(self
+                .call_optional::<_, i32>("getInputSize", ())?
+                .map(|size| size as u32))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::PrimeField;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn array32() {
+        // 12 limbs, as for the 381-bit prime of BLS12-381
+        let n32 = 12;
+        let value = BigInt::from(BigUint::from(ark_bls12_381::Fq::MODULUS));
+        let arr = to_array32(&value, n32).unwrap();
+        assert_eq!(arr.len(), n32);
+        assert_eq!(arr[0], 0x1a0111ea);
+        assert_eq!(from_array32(arr), value);
+
+        let small = to_array32(&BigInt::from(0x100000002u64), n32).unwrap();
+        assert_eq!(small[n32 - 2..], [1, 2]);
+        assert!(small[..n32 - 2].iter().all(|limb| *limb == 0));
+
+        // values which need more limbs are rejected rather than truncated
+        assert!(to_array32(&value, 8).is_err());
+        assert!(to_array32(&(BigInt::from(1) << (32 * n32)), n32).is_err());
+        assert!(to_array32(&BigInt::from(-1), n32).is_err());
+    }
+
+    #[cfg(feature = "wasmi")]
+    fn inputs(a: i64, b: i64) -> Vec<(String, Vec<BigInt>)> {
+        vec![
+            ("a".to_string(), vec![BigInt::from(a)]),
+            ("b".to_string(), vec![BigInt::from(b)]),
+        ]
+    }
+
+    #[cfg(feature = "wasmi")]
+    #[test]
+    fn multiplier() {
+        let mut runtime = WasmiRuntime::new("./test-vectors/circom2_multiplier2.wasm").unwrap();
+        let witness = calculate_witness_with(&mut runtime, inputs(3, 11), false).unwrap();
+        assert_eq!(witness[..4], [1, 33, 3, 11].map(BigInt::from));
+
+        // the instance can be reused, and negative inputs are reduced
+        let witness = calculate_witness_with(&mut runtime, inputs(-1, 2), false).unwrap();
+        assert_eq!(witness[1], read_prime_circom2(&mut runtime).unwrap() - 2);
+
+        let mut wrong = inputs(3, 11);
+        wrong[1].0 = "c".to_string();
+        let err = calculate_witness_with(&mut runtime, wrong, false).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<WitnessError>(),
+            Some(&WitnessError::UnknownInput("c".to_string()))
+        );
+    }
+
+    // the wasmer runtime, which cannot run in every environment
+    #[cfg(all(feature = "wasmi", feature = "wasmer"))]
+    #[test]
+    fn same_as_wasmer() {
+        let mut store = Store::default();
+        let mut wtns =
+            crate::WitnessCalculator::new(&mut store, "./test-vectors/circom2_multiplier2.wasm")
+                .unwrap();
+        let expected = wtns
+            .calculate_witness(&mut store, inputs(3, 11), true)
+            .unwrap();
+
+        let mut runtime = WasmiRuntime::new("./test-vectors/circom2_multiplier2.wasm").unwrap();
+        let witness = calculate_witness_with(&mut runtime, inputs(3, 11), true).unwrap();
+        assert_eq!(witness, expected);
+    }
+}
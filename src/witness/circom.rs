@@ -1,10 +1,14 @@
 use color_eyre::Result;
-use wasmer::{Exports, Function, Memory, Store, Value};
+use wasmer::{Exports, Function, FunctionEnv, Memory, Store, Value};
+
+use super::RuntimeEnv;
 
 #[derive(Debug)]
 pub struct Wasm {
     pub exports: Exports,
     pub memory: Memory,
+    // state of the runtime callbacks, if created by `WitnessCalculator::make_wasm_runtime`
+    pub(crate) runtime_env: Option<FunctionEnv<RuntimeEnv>>,
 }
 
 pub trait CircomBase {
@@ -38,6 +42,8 @@ pub trait Circom1 {
     fn get_ptr_raw_prime(&self, store: &mut Store) -> Result<u32>;
 }
 
+// The exports are documented on `CircomRuntime`, which `WasmerRuntime` implements with this trait
+#[cfg(feature = "circom-2")]
 pub trait Circom2 {
     fn get_field_num_len32(&self, store: &mut Store) -> Result<u32>;
     fn get_raw_prime(&self, store: &mut Store) -> Result<()>;
@@ -46,6 +52,9 @@ pub trait Circom2 {
     fn set_input_signal(&self, store: &mut Store, hmsb: u32, hlsb: u32, pos: u32) -> Result<()>;
     fn get_witness(&self, store: &mut Store, i: u32) -> Result<()>;
     fn get_witness_size(&self, store: &mut Store) -> Result<u32>;
+    fn get_input_signal_size(&self, store: &mut Store, hmsb: u32, hlsb: u32)
+        -> Result<Option<i32>>;
+    fn get_input_size(&self, store: &mut Store) -> Result<Option<u32>>;
 }
 
 impl Circom1 for Wasm {
@@ -144,6 +153,27 @@ impl Circom2 for Wasm {
     fn get_witness_size(&self, store: &mut Store) -> Result<u32> {
         self.get_u32(store, "getWitnessSize")
     }
+
+    fn get_input_signal_size(
+        &self,
+        store: &mut Store,
+        hmsb: u32,
+        hlsb: u32,
+    ) -> Result<Option<i32>> {
+        match self.exports.get_function("getInputSignalSize") {
+            Ok(func) => Ok(Some(
+                func.call(store, &[hmsb.into(), hlsb.into()])?[0].unwrap_i32(),
+            )),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn get_input_size(&self, store: &mut Store) -> Result<Option<u32>> {
+        match self.exports.get_function("getInputSize") {
+            Ok(func) => Ok(Some(func.call(store, &[])?[0].unwrap_i32() as u32)),
+            Err(_) => Ok(None),
+        }
+    }
 }
 
 impl CircomBase for Wasm {
@@ -180,6 +210,10 @@ impl CircomBase for Wasm {
 
 impl Wasm {
     pub fn new(exports: Exports, memory: Memory) -> Self {
-        Self { exports, memory }
+        Self {
+            exports,
+            memory,
+            runtime_env: None,
+        }
     }
 }
@@ -0,0 +1,61 @@
+//! Errors reported by the witness generators and in their inputs
+
+// Error type to signal end of execution, carrying the error code and message reported
+// by the circuit.
+// From https://docs.wasmer.io/integrations/examples/exit-early
+#[cfg(any(feature = "wasmer", feature = "circom-2"))]
+#[derive(thiserror::Error, Debug, Clone)]
+#[error("{message}")]
+pub(super) struct CircomError {
+    pub(super) code: i32,
+    pub(super) message: String,
+}
+
+#[cfg(any(feature = "wasmer", feature = "wasmi"))]
+impl CircomError {
+    // The error raised by the `exceptionHandler` of Circom 2, along with the messages
+    // printed before it
+    pub(super) fn exception(code: i32, message: &str) -> Self {
+        let error = match code {
+            1 => "Signal not found.",
+            2 => "Too many signals set.",
+            3 => "Signal already set.",
+            4 => "Assert Failed.",
+            5 => "Not enough memory.",
+            6 => "Input signal array access exceeds the size.",
+            _ => "Unknown error.",
+        };
+        Self {
+            code,
+            message: format!("{error}\n{message}").trim_end().to_string(),
+        }
+    }
+}
+
+// Circom 1 error code for a name which does not hash to a signal of the component
+#[cfg(feature = "wasmer")]
+pub(super) const HASH_NOT_FOUND: i32 = 3;
+
+// Circom 2 exception code for an unknown input signal
+#[cfg(feature = "circom-2")]
+pub(super) const SIGNAL_NOT_FOUND: i32 = 1;
+
+/// Errors in the inputs provided to calculate a witness, e.g. with
+/// [`calculate_witness_with`](crate::calculate_witness_with), which can be recovered from
+/// the returned error with `downcast_ref`
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum WitnessError {
+    /// The circuit has no input signal with this name
+    #[error("unknown input signal `{0}`")]
+    UnknownInput(String),
+    /// The number of values provided for an input signal does not match its size
+    #[error("input signal `{name}` expects {expected} values, but {actual} were provided")]
+    InputLength {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+    /// The total number of input values does not match the number of inputs of the circuit
+    #[error("the circuit expects {expected} input values, but {actual} were provided")]
+    InputCount { expected: usize, actual: usize },
+}
@@ -1,19 +1,17 @@
 //! Safe-ish interface for reading and writing specific types to the WASM runtime's memory
-use ark_serialize::CanonicalDeserialize;
 use num_traits::ToPrimitive;
-use wasmer::{Memory, MemoryAccessError, MemoryView, Store};
+use wasmer::{AsStoreRef, Memory, MemoryAccessError, MemoryView, Store};
 
 // TODO: Decide whether we want Ark here or if it should use a generic BigInt package
 use ark_bn254::FrConfig;
 use ark_ff::MontConfig;
-use ark_ff::{BigInteger, BigInteger256, Zero};
+use ark_ff::Zero;
 
-use num_bigint::{BigInt, BigUint};
+use num::Integer;
+use num_bigint::{BigInt, BigUint, Sign};
 
-use color_eyre::Result;
-use std::io::Cursor;
-use std::str::FromStr;
-use std::{convert::TryFrom, ops::Deref};
+use color_eyre::{eyre::eyre, Result};
+use std::ops::Deref;
 
 #[derive(Debug)]
 pub struct SafeMemory {
@@ -24,6 +22,8 @@ pub struct SafeMemory {
     short_min: BigInt,
     r_inv: BigInt,
     n32: usize,
+    // the free position once the wasm is instantiated, which a soft reset goes back to
+    pub(super) initial_free_pos: u32,
 }
 
 impl Deref for SafeMemory {
@@ -42,10 +42,7 @@ impl SafeMemory {
         let short_min =
             BigInt::from_biguint(num_bigint::Sign::NoSign, BigUint::from(FrConfig::MODULUS))
                 - &short_max;
-        let r_inv = BigInt::from_str(
-            "9915499612839321149637521777990102151350674507940716049588462388200839649614",
-        )
-        .unwrap();
+        let r_inv = r_inv(&prime, n32);
 
         Self {
             memory,
@@ -55,9 +52,16 @@ impl SafeMemory {
             short_min,
             r_inv,
             n32,
+            initial_free_pos: 0,
         }
     }
 
+    /// Sets the prime of the field, e.g. once it was read from the memory
+    pub fn set_prime(&mut self, prime: BigInt) {
+        self.r_inv = r_inv(&prime, self.n32);
+        self.prime = prime;
+    }
+
     /// Gets an immutable view to the memory in 32 byte chunks
     pub fn view<'a>(&self, store: &'a mut Store) -> MemoryView<'a> {
         self.memory.view(store)
@@ -92,15 +96,15 @@ impl SafeMemory {
     }
 
     /// Reads a u32 from the specified memory offset
-    pub fn read_u32(&self, store: &mut Store, ptr: usize) -> Result<u32, MemoryAccessError> {
+    pub fn read_u32(&self, store: &impl AsStoreRef, ptr: usize) -> Result<u32, MemoryAccessError> {
         let mut bytes = [0; 4];
-        self.view(store).read(ptr as u64, &mut bytes)?;
+        self.memory.view(store).read(ptr as u64, &mut bytes)?;
         Ok(u32::from_le_bytes(bytes))
     }
 
-    pub fn read_byte(&self, store: &mut Store, ptr: usize) -> Result<u8, MemoryAccessError> {
+    pub fn read_byte(&self, store: &impl AsStoreRef, ptr: usize) -> Result<u8, MemoryAccessError> {
         let mut bytes = [0; 1];
-        self.view(store).read(ptr as u64, &mut bytes)?;
+        self.memory.view(store).read(ptr as u64, &mut bytes)?;
         Ok(u8::from_le_bytes(bytes))
     }
 
@@ -112,7 +116,9 @@ impl SafeMemory {
     }
 
     /// Writes a Field Element to memory at the specified offset, truncating
-    /// to smaller u32 types if needed and adjusting the sign via 2s complement
+    /// to smaller u32 types if needed and adjusting the sign via 2s complement.
+    /// Negative values which do not fit in an i32 are reduced modulo the prime,
+    /// e.g. `-p - 1` is written as `p - 1`.
     pub fn write_fr(&self, store: &mut Store, ptr: usize, fr: &BigInt) -> Result<()> {
         if fr < &self.short_max && fr > &self.short_min {
             if fr >= &BigInt::zero() {
@@ -120,6 +126,8 @@ impl SafeMemory {
             } else {
                 self.write_short_negative(store, ptr, fr)?;
             }
+        } else if fr.sign() == Sign::Minus {
+            self.write_long_normal(store, ptr, &fr.mod_floor(&self.prime))?;
         } else {
             self.write_long_normal(store, ptr, fr)?;
         }
@@ -128,12 +136,16 @@ impl SafeMemory {
     }
 
     /// Reads a Field Element from the memory at the specified offset
-    pub fn read_fr(&self, store: &mut Store, ptr: usize) -> Result<BigInt, MemoryAccessError> {
+    pub fn read_fr(
+        &self,
+        store: &impl AsStoreRef,
+        ptr: usize,
+    ) -> Result<BigInt, MemoryAccessError> {
         let test_byte = self.read_byte(store, ptr + 4 + 3)?;
         let test_byte2 = self.read_byte(store, ptr + 3)?;
 
         if test_byte & 0x80 != 0 {
-            let mut num = self.read_big(store, ptr + 8, self.n32)?;
+            let mut num = self.read_big(store, ptr + 8)?;
             if test_byte & 0x40 != 0 {
                 num = (num * &self.r_inv) % &self.prime
             }
@@ -177,33 +189,37 @@ impl SafeMemory {
         Ok(())
     }
 
-    fn write_big(
-        &self,
-        store: &mut Store,
-        ptr: usize,
-        num: &BigInt,
-    ) -> Result<(), MemoryAccessError> {
-        let (_, num) = num.clone().into_parts();
-        let num = BigInteger256::try_from(num).unwrap();
-
-        let bytes = num.to_bytes_le();
-        self.view(store).write(ptr as u64, &bytes)
+    fn write_big(&self, store: &mut Store, ptr: usize, num: &BigInt) -> Result<()> {
+        let (_, mut bytes) = num.to_bytes_le();
+        if bytes.len() > self.n32 * 4 {
+            return Err(eyre!("{num} does not fit in {} 32-bit limbs", self.n32));
+        }
+        bytes.resize(self.n32 * 4, 0);
+        self.view(store).write(ptr as u64, &bytes)?;
+        Ok(())
     }
 
-    /// Reads `num_bytes * 32` from the specified memory offset in a Big Integer
+    /// Reads a Big Integer of `n32` 32-bit limbs from the specified memory offset
     pub fn read_big(
         &self,
-        store: &mut Store,
+        store: &impl AsStoreRef,
         ptr: usize,
-        num_bytes: usize,
     ) -> Result<BigInt, MemoryAccessError> {
-        let mut buf = vec![0; num_bytes * 32];
-        self.view(store).read(ptr as u64, &mut buf)?;
-        // TODO: Is there a better way to read big integers?
-        let big = BigInteger256::deserialize_uncompressed(&mut Cursor::new(buf)).unwrap();
-        let big = BigUint::from(big);
-        Ok(big.into())
+        let mut buf = vec![0; self.n32 * 4];
+        self.memory.view(store).read(ptr as u64, &mut buf)?;
+        Ok(BigUint::from_bytes_le(&buf).into())
+    }
+}
+
+// Returns the inverse of the Montgomery factor `R = 2^(32 * n32)` modulo the prime, which
+// converts the Montgomery form of the field elements in the memory back to their value
+fn r_inv(prime: &BigInt, n32: usize) -> BigInt {
+    if prime.is_zero() {
+        return BigInt::zero();
     }
+    // the prime is prime, so R^(p - 2) is the inverse of R
+    let r = BigInt::from(1) << (32 * n32);
+    r.modpow(&(prime - 2), prime)
 }
 
 // TODO: Figure out how to read / write numbers > u32
@@ -224,7 +240,7 @@ mod tests {
         let mut store = Store::default();
         let mem = SafeMemory::new(
             Memory::new(&mut store, MemoryType::new(1, None, false)).unwrap(),
-            2,
+            8,
             BigInt::from_str(
                 "21888242871839275222246405745257275088548364400416034343698204186575808495617",
             )
@@ -241,16 +257,32 @@ mod tests {
         assert_eq!(mem.short_max.to_i64().unwrap(), i32_max);
     }
 
+    #[test]
+    fn computed_r_inv() {
+        let prime = BigInt::from_str(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+        )
+        .unwrap();
+        assert_eq!(
+            r_inv(&prime, 8),
+            BigInt::from_str(
+                "9915499612839321149637521777990102151350674507940716049588462388200839649614"
+            )
+            .unwrap()
+        );
+        assert_eq!((r_inv(&prime, 8) << 256) % &prime, BigInt::from(1));
+    }
+
     #[test]
     fn read_write_32() {
         let (mem, mut store) = new();
         let num = u32::MAX;
 
-        let inp = mem.read_u32(&mut store, 0).unwrap();
+        let inp = mem.read_u32(&store, 0).unwrap();
         assert_eq!(inp, 0);
 
         mem.write_u32(&mut store, 0, num).unwrap();
-        let inp = mem.read_u32(&mut store, 0).unwrap();
+        let inp = mem.read_u32(&store, 0).unwrap();
         assert_eq!(inp, num);
     }
 
@@ -269,17 +301,49 @@ mod tests {
         read_write_fr(BigInt::from(500000000000i64));
     }
 
-    // TODO: How should this be handled?
     #[test]
-    #[ignore]
     fn read_write_fr_big_negative() {
-        read_write_fr(BigInt::from_str("-500000000000").unwrap())
+        let (mem, mut store) = new();
+        let num = BigInt::from_str("-500000000000").unwrap();
+        mem.write_fr(&mut store, 0, &num).unwrap();
+        // it is read back as the field element it was reduced to
+        let res = mem.read_fr(&store, 0).unwrap();
+        assert_eq!(res, &mem.prime + &num);
+
+        // including values beyond the prime
+        let num = -&mem.prime * 3 - 1;
+        mem.write_fr(&mut store, 0, &num).unwrap();
+        let res = mem.read_fr(&store, 0).unwrap();
+        assert_eq!(res, &mem.prime - 1);
+    }
+
+    #[test]
+    fn read_big_out_of_bounds() {
+        let (mem, mut store) = new();
+        let size = mem.view(&mut store).data_size() as usize;
+        // a number takes n32 words, so the last one starts 4 * n32 bytes before the end
+        assert!(mem.read_big(&store, size - 4 * mem.n32).is_ok());
+        assert!(mem.read_big(&store, size - 4 * mem.n32 + 4).is_err());
+        assert!(mem.read_big(&store, size).is_err());
+    }
+
+    #[test]
+    fn read_write_big_wide_field() {
+        // 12 limbs, as for a 381-bit prime
+        let (mut mem, mut store) = new();
+        mem.n32 = 12;
+        let num = (BigInt::from(1) << 380) + 7;
+        mem.write_big(&mut store, 8, &num).unwrap();
+        assert_eq!(mem.read_big(&store, 8).unwrap(), num);
+        assert!(mem
+            .write_big(&mut store, 8, &(BigInt::from(1) << 384))
+            .is_err());
     }
 
     fn read_write_fr(num: BigInt) {
         let (mem, mut store) = new();
         mem.write_fr(&mut store, 0, &num).unwrap();
-        let res = mem.read_fr(&mut store, 0).unwrap();
+        let res = mem.read_fr(&store, 0).unwrap();
         assert_eq!(res, num);
     }
 }
@@ -1,21 +1,69 @@
+mod error;
+pub use error::WitnessError;
+
+#[cfg(feature = "wasmer")]
 mod witness_calculator;
+#[cfg(feature = "wasmer")]
+use witness_calculator::RuntimeEnv;
+#[cfg(feature = "wasmer")]
 pub use witness_calculator::WitnessCalculator;
 
+#[cfg(feature = "wasmer")]
+mod pool;
+#[cfg(feature = "wasmer")]
+pub use pool::{PooledWitnessCalculator, WitnessCalculatorPool};
+
+mod wtns;
+pub use wtns::{read_wtns, write_wtns};
+
+#[cfg(feature = "native")]
+mod native;
+#[cfg(all(test, unix, feature = "native", feature = "wasmer"))]
+pub(crate) use native::tests::fake_generator;
+#[cfg(feature = "native")]
+pub use native::NativeWitnessCalculator;
+
+#[cfg(feature = "wasmer")]
 mod memory;
+#[cfg(feature = "wasmer")]
 pub(super) use memory::SafeMemory;
 
+#[cfg(feature = "wasmer")]
 mod circom;
+#[cfg(feature = "wasmer")]
 pub(super) use circom::CircomBase;
+#[cfg(feature = "wasmer")]
 pub use circom::Wasm;
 
-#[cfg(feature = "circom-2")]
+#[cfg(all(feature = "wasmer", feature = "circom-2"))]
 pub(super) use circom::Circom2;
 
+#[cfg(feature = "circom-2")]
+mod backend;
+#[cfg(all(feature = "wasmer", feature = "circom-2"))]
+pub use backend::WasmerRuntime;
+#[cfg(feature = "wasmi")]
+pub use backend::WasmiRuntime;
+#[cfg(feature = "circom-2")]
+pub use backend::{calculate_witness_with, CircomRuntime};
+
+#[cfg(feature = "wasmer")]
 pub(super) use circom::Circom1;
 
+// Number of wasm pages allocated for the memory of the witness generators by default
+#[cfg(any(feature = "wasmer", feature = "wasmi"))]
+const DEFAULT_MEMORY_PAGES: u32 = 2000;
+
+#[cfg(any(feature = "wasmer", feature = "circom-2"))]
 use fnv::FnvHasher;
+#[cfg(any(feature = "wasmer", feature = "circom-2"))]
 use std::hash::Hasher;
 
+/// Hashes a signal name with 64-bit FNV-1a, as circom does to look up input signals.
+/// Any string can be hashed, but only the exact name of a signal of the main component
+/// matches: the elements of an array signal share the hash of its base name and are
+/// addressed by their position instead.
+#[cfg(any(feature = "wasmer", feature = "circom-2"))]
 pub(crate) fn fnv(inp: &str) -> (u32, u32) {
     let mut hasher = FnvHasher::default();
     hasher.write(inp.as_bytes());
@@ -23,3 +71,14 @@ pub(crate) fn fnv(inp: &str) -> (u32, u32) {
 
     ((h >> 32) as u32, h as u32)
 }
+
+#[cfg(all(test, any(feature = "wasmer", feature = "circom-2")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv_hash() {
+        assert_eq!(fnv("in"), (0x08b73807, 0xb55c4bbe));
+        assert_eq!(fnv("comp.x"), (0x5188b17c, 0x8a65ae5c));
+    }
+}
@@ -0,0 +1,192 @@
+//! Witness calculation with the native witness generator that circom compiles from C++
+//!
+//! `circom --c` emits a C++ program which, once built with `make`, is run as
+//! `./circuit input.json output.wtns`. It computes the same witness as the wasm
+//! generator, but is much faster for large circuits.
+use color_eyre::{eyre::eyre, Result};
+use num::Integer;
+use num_bigint::{BigInt, Sign};
+use serde_json::{Map, Value};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::wtns::read_wtns_bigint;
+
+/// Calculates witnesses by running a witness generator compiled with `circom --c`. It
+/// takes the same inputs as [`WitnessCalculator`](super::WitnessCalculator) and returns
+/// the same witness, so the two can be used interchangeably.
+#[derive(Clone, Debug)]
+pub struct NativeWitnessCalculator {
+    binary: PathBuf,
+    prime: Option<BigInt>,
+}
+
+impl NativeWitnessCalculator {
+    /// Uses the compiled witness generator at `binary`
+    pub fn new(binary: impl AsRef<Path>) -> Self {
+        Self {
+            binary: binary.as_ref().to_path_buf(),
+            prime: None,
+        }
+    }
+
+    /// Sets the prime of the circuit. The witnesses computed by the generator are then
+    /// checked to be for this prime, and negative inputs are reduced modulo it, which they
+    /// must be to be passed to the generator.
+    pub fn with_prime(mut self, prime: BigInt) -> Self {
+        self.prime = Some(prime);
+        self
+    }
+
+    /// Returns the path of the compiled witness generator
+    pub fn binary(&self) -> &Path {
+        &self.binary
+    }
+
+    /// Runs the witness generator on `inputs` and returns the witness it computes
+    pub fn calculate_witness<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &self,
+        inputs: I,
+    ) -> Result<Vec<BigInt>> {
+        // a fresh directory only accessible to the current user, removed when dropped
+        let dir = tempfile::tempdir()?;
+        self.run(
+            inputs,
+            &dir.path().join("input.json"),
+            &dir.path().join("output.wtns"),
+        )
+    }
+
+    fn run<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &self,
+        inputs: I,
+        input: &Path,
+        output: &Path,
+    ) -> Result<Vec<BigInt>> {
+        let json = self.inputs_json(inputs)?;
+        std::fs::write(input, serde_json::to_vec(&json)?)?;
+
+        let result = Command::new(&self.binary)
+            .arg(input)
+            .arg(output)
+            .output()
+            .map_err(|err| eyre!("could not run {}: {err}", self.binary.display()))?;
+        if !result.status.success() {
+            return Err(eyre!(
+                "the witness generator {} failed with {}: {}",
+                self.binary.display(),
+                result.status,
+                String::from_utf8_lossy(&result.stderr).trim()
+            ));
+        }
+
+        let (prime, witness) = read_wtns_bigint(&mut BufReader::new(File::open(output)?))?;
+        if let Some(expected) = &self.prime {
+            if &prime != expected {
+                return Err(eyre!(
+                    "the witness generator is for the field of prime {prime}, not {expected}"
+                ));
+            }
+        }
+        Ok(witness)
+    }
+
+    // The generator reads each signal as a decimal string, or as a (flat) array of them
+    // for array signals
+    fn inputs_json<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &self,
+        inputs: I,
+    ) -> Result<Value> {
+        let mut json = Map::new();
+        for (name, values) in inputs {
+            let mut values = values
+                .iter()
+                .map(|value| self.input_value(value).map(Value::String))
+                .collect::<Result<Vec<_>>>()
+                .map_err(|err| eyre!("invalid value for input `{name}`: {err}"))?;
+            let value = if values.len() == 1 {
+                values.remove(0)
+            } else {
+                Value::Array(values)
+            };
+            json.insert(name, value);
+        }
+        Ok(Value::Object(json))
+    }
+
+    fn input_value(&self, value: &BigInt) -> Result<String> {
+        if value.sign() != Sign::Minus {
+            return Ok(value.to_string());
+        }
+        match &self.prime {
+            Some(prime) => Ok(value.mod_floor(prime).to_string()),
+            None => Err(eyre!("negative values need the prime of the circuit")),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+pub(crate) mod tests {
+    use super::*;
+    use crate::write_wtns;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    // Writes a shell script standing in for a compiled generator, which outputs `witness`
+    // and saves the inputs it is given next to itself. The script lives in the returned
+    // directory, which is removed when dropped.
+    pub(crate) fn fake_generator(prime: &BigInt, witness: &[BigInt]) -> (TempDir, PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let wtns = dir.join("witness.wtns");
+        write_wtns(&mut File::create(&wtns).unwrap(), prime, witness).unwrap();
+
+        let binary = dir.join("circuit");
+        let script = format!(
+            "#!/bin/sh\ncp \"$1\" {}\ncp {} \"$2\"\n",
+            dir.join("input.json").display(),
+            wtns.display()
+        );
+        std::fs::write(&binary, script).unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        (tmp, binary)
+    }
+
+    #[test]
+    fn calculate_witness() {
+        let prime = BigInt::from(101);
+        let witness = [1, 33, 3, 11].map(BigInt::from);
+        let (dir, binary) = fake_generator(&prime, &witness);
+        let native = NativeWitnessCalculator::new(&binary).with_prime(prime);
+
+        let inputs = [
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(-1), BigInt::from(11)]),
+        ];
+        assert_eq!(native.calculate_witness(inputs).unwrap(), witness);
+
+        let input = std::fs::read(binary.with_file_name("input.json")).unwrap();
+        let input: Value = serde_json::from_slice(&input).unwrap();
+        assert_eq!(input, serde_json::json!({ "a": "3", "b": ["100", "11"] }));
+
+        // the witness must be for the prime of the circuit
+        let native = native.with_prime(BigInt::from(103));
+        assert!(native.calculate_witness([]).is_err());
+
+        // and negative values cannot be reduced without it
+        let native = NativeWitnessCalculator::new(&binary);
+        assert!(native
+            .calculate_witness([("a".to_string(), vec![BigInt::from(-1)])])
+            .is_err());
+
+        // failures of the generator are reported
+        let native = NativeWitnessCalculator::new(binary.with_file_name("missing"));
+        assert!(native.calculate_witness([]).is_err());
+
+        dir.close().unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+use color_eyre::Result;
+use std::sync::{Condvar, Mutex};
+use wasmer::{Engine, Module, Store};
+
+use super::WitnessCalculator;
+
+/// A bounded pool of [`WitnessCalculator`]s for the same circuit, which can be shared
+/// between threads. The wasm is compiled once, and instances are created on demand up to
+/// the size of the pool, then handed out again once they are returned.
+#[derive(Debug)]
+pub struct WitnessCalculatorPool {
+    engine: Engine,
+    module: Module,
+    size: usize,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    idle: Vec<(WitnessCalculator, Store)>,
+    created: usize,
+}
+
+impl WitnessCalculatorPool {
+    /// Compiles the witness generator at `path` for a pool of at most `size` instances
+    pub fn new(path: impl AsRef<std::path::Path>, size: usize) -> Result<Self> {
+        Self::from_bytes(std::fs::read(path)?, size)
+    }
+
+    /// Same as [`new`](Self::new), but from the bytes of the wasm file
+    pub fn from_bytes(bytes: impl AsRef<[u8]>, size: usize) -> Result<Self> {
+        let engine = Store::default().engine().clone();
+        let module = Module::new(&engine, bytes)?;
+        Ok(Self::from_module(engine, module, size))
+    }
+
+    /// Creates a pool of at most `size` instances of a module compiled with `engine`
+    pub fn from_module(engine: Engine, module: Module, size: usize) -> Self {
+        assert!(size > 0, "the pool must hold at least one instance");
+        Self {
+            engine,
+            module,
+            size,
+            state: Mutex::new(PoolState::default()),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Takes a calculator out of the pool, instantiating a new one if none is idle and
+    /// the pool is not full yet, or else blocking until another thread returns one. The
+    /// calculator goes back to the pool when the returned guard is dropped.
+    pub fn acquire(&self) -> Result<PooledWitnessCalculator<'_>> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(entry) = state.idle.pop() {
+                return Ok(PooledWitnessCalculator {
+                    pool: self,
+                    entry: Some(entry),
+                });
+            }
+            if state.created < self.size {
+                break;
+            }
+            state = self.available.wait(state).unwrap();
+        }
+        state.created += 1;
+        drop(state);
+
+        // instantiate without holding the lock, so that other threads can return theirs
+        let mut store = Store::new(self.engine.clone());
+        match WitnessCalculator::from_module(&mut store, self.module.clone()) {
+            Ok(wtns) => Ok(PooledWitnessCalculator {
+                pool: self,
+                entry: Some((wtns, store)),
+            }),
+            Err(err) => {
+                self.discard();
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns the number of instances created so far, at most the size of the pool
+    pub fn num_instances(&self) -> usize {
+        self.state.lock().unwrap().created
+    }
+
+    fn release(&self, entry: (WitnessCalculator, Store)) {
+        self.state.lock().unwrap().idle.push(entry);
+        self.available.notify_one();
+    }
+
+    // frees the slot of an instance which is not returned, so that another can be created
+    fn discard(&self) {
+        self.state.lock().unwrap().created -= 1;
+        self.available.notify_one();
+    }
+}
+
+/// A [`WitnessCalculator`] borrowed from a [`WitnessCalculatorPool`], which is returned to
+/// the pool on drop
+#[derive(Debug)]
+pub struct PooledWitnessCalculator<'a> {
+    pool: &'a WitnessCalculatorPool,
+    entry: Option<(WitnessCalculator, Store)>,
+}
+
+impl PooledWitnessCalculator<'_> {
+    /// Returns the calculator along with its store, e.g. for
+    /// `let (wtns, store) = guard.get_mut(); wtns.calculate_witness(store, inputs, false)`
+    pub fn get_mut(&mut self) -> (&mut WitnessCalculator, &mut Store) {
+        let (wtns, store) = self.entry.as_mut().unwrap();
+        (wtns, store)
+    }
+}
+
+impl Drop for PooledWitnessCalculator<'_> {
+    fn drop(&mut self) {
+        let (mut wtns, mut store) = self.entry.take().unwrap();
+        // the memory of an instance which cannot be reset is left in an unknown state
+        match wtns.soft_reset(&mut store) {
+            Ok(()) => self.pool.release((wtns, store)),
+            Err(_) => self.pool.discard(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{groth16_prove, mycircuit_proof};
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::{prepare_verifying_key, Groth16};
+    use ark_std::rand::thread_rng;
+    use num_bigint::BigInt;
+
+    #[test]
+    fn reuses_instances() {
+        let (params, matrices, _, _) = mycircuit_proof();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let pool = WitnessCalculatorPool::new("./test-vectors/mycircuit.wasm", 2).unwrap();
+        std::thread::scope(|s| {
+            for i in 0..8u64 {
+                let (pool, params, matrices, pvk) = (&pool, &params, &matrices, &pvk);
+                s.spawn(move || {
+                    let mut guard = pool.acquire().unwrap();
+                    let (wtns, store) = guard.get_mut();
+                    let inputs = [
+                        ("a".to_string(), vec![BigInt::from(3)]),
+                        ("b".to_string(), vec![BigInt::from(i)]),
+                    ];
+                    let full_assignment = wtns
+                        .calculate_witness_element::<Fr, _>(store, inputs, false)
+                        .unwrap();
+                    drop(guard);
+
+                    let proof =
+                        groth16_prove(params, matrices, &full_assignment, &mut thread_rng())
+                            .unwrap();
+                    let public = [Fr::from(3 * i)];
+                    assert!(Groth16::<Bn254>::verify_proof(pvk, &proof, &public).unwrap());
+                });
+            }
+        });
+        assert!(pool.num_instances() <= 2);
+    }
+}
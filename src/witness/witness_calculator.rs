@@ -1,17 +1,25 @@
-use super::{fnv, CircomBase, SafeMemory, Wasm};
+use super::{
+    error::{CircomError, HASH_NOT_FOUND},
+    fnv, CircomBase, SafeMemory, Wasm, WitnessError,
+};
 use ark_ff::PrimeField;
-use color_eyre::Result;
-use num_bigint::BigInt;
+use color_eyre::{eyre::eyre, Result};
+use num_bigint::{BigInt, BigUint};
 use num_traits::Zero;
-use wasmer::{imports, Function, Instance, Memory, MemoryType, Module, RuntimeError, Store};
+use wasmer::{
+    imports, Exports, Function, FunctionEnv, FunctionEnvMut, Instance, Memory, MemoryType, Module,
+    RuntimeError, Store, Value,
+};
 use wasmer_wasix::WasiEnv;
 
-#[cfg(feature = "circom-2")]
-use num::ToPrimitive;
-
 use super::Circom1;
 #[cfg(feature = "circom-2")]
-use super::Circom2;
+use super::{
+    backend::{
+        read_prime_circom2, read_witness_circom2, read_witness_elements_circom2, set_inputs_circom2,
+    },
+    CircomRuntime, WasmerRuntime,
+};
 
 #[derive(Debug)]
 pub struct WitnessCalculator {
@@ -22,38 +30,10 @@ pub struct WitnessCalculator {
     pub prime: BigInt,
 }
 
-// Error type to signal end of execution.
-// From https://docs.wasmer.io/integrations/examples/exit-early
-#[derive(thiserror::Error, Debug, Clone, Copy)]
-#[error("{0}")]
-struct ExitCode(u32);
-
-#[cfg(feature = "circom-2")]
-fn from_array32(arr: Vec<u32>) -> BigInt {
-    let mut res = BigInt::zero();
-    let radix = BigInt::from(0x100000000u64);
-    for &val in arr.iter() {
-        res = res * &radix + BigInt::from(val);
-    }
-    res
-}
-
-#[cfg(feature = "circom-2")]
-fn to_array32(s: &BigInt, size: usize) -> Vec<u32> {
-    let mut res = vec![0; size];
-    let mut rem = s.clone();
-    let radix = BigInt::from(0x100000000u64);
-    let mut c = size;
-    while !rem.is_zero() {
-        c -= 1;
-        res[c] = (&rem % &radix).to_u32().unwrap();
-        rem /= &radix;
-    }
-
-    res
-}
-
 impl WitnessCalculator {
+    /// Number of wasm pages allocated for the runtime's memory by default, i.e. 125 MiB
+    pub const DEFAULT_MEMORY_PAGES: u32 = super::DEFAULT_MEMORY_PAGES;
+
     pub fn new(store: &mut Store, path: impl AsRef<std::path::Path>) -> Result<Self> {
         Self::from_file(store, path)
     }
@@ -63,36 +43,88 @@ impl WitnessCalculator {
         Self::from_module(store, module)
     }
 
+    /// Compiles the witness generator from the bytes of its wasm file, e.g. when it
+    /// was fetched over the network rather than read from disk
+    pub fn from_bytes(store: &mut Store, bytes: impl AsRef<[u8]>) -> Result<Self> {
+        let module = Module::new(&store, bytes)?;
+        Self::from_module(store, module)
+    }
+
     pub fn from_module(store: &mut Store, module: Module) -> Result<Self> {
-        let wasm = Self::make_wasm_runtime(store, module)?;
+        Self::from_module_with_memory_pages(store, module, Self::DEFAULT_MEMORY_PAGES)
+    }
+
+    /// Same as [`from_module`](Self::from_module), but allocates `pages` wasm pages of
+    /// 64 KiB for the runtime's memory instead of [`DEFAULT_MEMORY_PAGES`](Self::DEFAULT_MEMORY_PAGES).
+    /// Circuits failing with an out of bounds memory access need more, while fewer pages
+    /// than the minimum declared by the module are rejected when instantiating it.
+    pub fn from_module_with_memory_pages(
+        store: &mut Store,
+        module: Module,
+        pages: u32,
+    ) -> Result<Self> {
+        let wasm = Self::make_wasm_runtime_with_memory_pages(store, module, pages)?;
         Self::new_from_wasm(store, wasm)
     }
 
     pub fn make_wasm_runtime(store: &mut Store, module: Module) -> Result<Wasm> {
-        let memory = Memory::new(store, MemoryType::new(2000, None, false)).unwrap();
+        Self::make_wasm_runtime_with_memory_pages(store, module, Self::DEFAULT_MEMORY_PAGES)
+    }
+
+    /// Same as [`make_wasm_runtime`](Self::make_wasm_runtime), with `pages` wasm pages of
+    /// memory
+    pub fn make_wasm_runtime_with_memory_pages(
+        store: &mut Store,
+        module: Module,
+        pages: u32,
+    ) -> Result<Wasm> {
+        let memory = Memory::new(store, MemoryType::new(pages, None, false))?;
+        let env = FunctionEnv::new(store, runtime::RuntimeEnv::new(memory.clone()));
+        // Circom 1 imports `error` and the `log*` functions, while Circom 2 imports
+        // `exceptionHandler`, `showSharedRWMemory`, `printErrorMessage`,
+        // `writeBufferMessage` and, since 2.2.0, `printDebug`
         let import_object = imports! {
             "env" => {
                 "memory" => memory.clone(),
             },
             // Host function callbacks from the WASM
             "runtime" => {
-                "error" => runtime::error(store),
-                "logSetSignal" => runtime::log_signal(store),
-                "logGetSignal" => runtime::log_signal(store),
-                "logFinishComponent" => runtime::log_component(store),
-                "logStartComponent" => runtime::log_component(store),
-                "log" => runtime::log_component(store),
-                "exceptionHandler" => runtime::exception_handler(store),
-                "showSharedRWMemory" => runtime::show_memory(store),
-                "printErrorMessage" => runtime::print_error_message(store),
-                "writeBufferMessage" => runtime::write_buffer_message(store),
+                "error" => runtime::error(store, &env),
+                "logSetSignal" => runtime::log_set_signal(store, &env),
+                "logGetSignal" => runtime::log_get_signal(store, &env),
+                "logFinishComponent" => runtime::log_finish_component(store, &env),
+                "logStartComponent" => runtime::log_start_component(store, &env),
+                "log" => runtime::log(store, &env),
+                "exceptionHandler" => runtime::exception_handler(store, &env),
+                "showSharedRWMemory" => runtime::show_memory(store, &env),
+                "printErrorMessage" => runtime::print_error_message(store, &env),
+                "writeBufferMessage" => runtime::write_buffer_message(store, &env),
+                "printDebug" => runtime::print_debug(store, &env),
             }
         };
+
+        // report every missing import at once, rather than only the first one like
+        // instantiating the module would
+        let missing = module
+            .imports()
+            .filter(|import| !import_object.exists(import.module(), import.name()))
+            .map(|import| format!("{}::{} ({:?})", import.module(), import.name(), import.ty()))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(eyre!(
+                "the wasm requires imports which are not provided: {}",
+                missing.join(", ")
+            ));
+        }
+
         let instance = Instance::new(store, &module, &import_object)?;
         let exports = instance.exports.clone();
+        // Circom 2 messages are read back through the module's exports
+        env.as_mut(store).exports = Some(exports.clone());
         let mut wasi_env = WasiEnv::builder("calculateWitness").finalize(store)?;
         wasi_env.initialize_with_memory(store, instance, Some(memory.clone()), false)?;
-        let wasm = Wasm::new(exports, memory);
+        let mut wasm = Wasm::new(exports, memory);
+        wasm.runtime_env = Some(env);
         Ok(wasm)
     }
 
@@ -105,14 +137,7 @@ impl WitnessCalculator {
             store: &mut Store,
             version: u32,
         ) -> Result<WitnessCalculator> {
-            let n32 = instance.get_field_num_len32(store)?;
-            instance.get_raw_prime(store)?;
-            let mut arr = vec![0; n32 as usize];
-            for i in 0..n32 {
-                let res = instance.read_shared_rw_memory(store, i)?;
-                arr[(n32 as usize) - (i as usize) - 1] = res;
-            }
-            let prime = from_array32(arr);
+            let prime = read_prime_circom2(&mut WasmerRuntime::new(&instance, store))?;
 
             let n64 = ((prime.bits() - 1) / 64 + 1) as u32;
 
@@ -135,10 +160,20 @@ impl WitnessCalculator {
             let mut safe_memory =
                 SafeMemory::new(instance.memory.clone(), n32 as usize, BigInt::zero());
             let ptr = instance.get_ptr_raw_prime(store)?;
-            let prime = safe_memory.read_big(store, ptr as usize, n32 as usize)?;
+            let prime = safe_memory.read_big(store, ptr as usize)?;
 
             let n64 = ((prime.bits() - 1) / 64 + 1) as u32;
-            safe_memory.prime = prime.clone();
+            safe_memory.set_prime(prime.clone());
+            safe_memory.initial_free_pos = safe_memory.free_pos(store)?;
+
+            // the field elements passed to the callbacks are read with a memory of their own
+            if let Some(env) = &instance.runtime_env {
+                env.as_mut(store).fr_memory = Some(SafeMemory::new(
+                    instance.memory.clone(),
+                    n32 as usize,
+                    prime.clone(),
+                ));
+            }
 
             Ok(WitnessCalculator {
                 instance,
@@ -165,39 +200,180 @@ impl WitnessCalculator {
                     _ => panic!("Unknown Circom version")
                 }
             } else {
-                new_circom1(instance, memory, version)
+                new_circom1(wasm, store, version)
             }
         }
     }
 
+    /// Calculates the witness for the provided inputs. Inputs are keyed by the name of the
+    /// signal in the main component, e.g. `in` rather than `main.in`, and array signals
+    /// must be provided under their base name with all their elements in row-major order,
+    /// e.g. `("in", vec![in0, in1])` rather than `in[0]` and `in[1]` separately.
     pub fn calculate_witness<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
         &mut self,
         store: &mut Store,
         inputs: I,
         sanity_check: bool,
     ) -> Result<Vec<BigInt>> {
-        self.instance.init(store, sanity_check)?;
+        let mut witness = Vec::new();
+        self.calculate_witness_into(store, inputs, sanity_check, &mut witness)?;
+        Ok(witness)
+    }
+
+    /// Same as [`calculate_witness`](Self::calculate_witness), but writes the witness to
+    /// `out`, replacing its contents. Reusing the same buffer across calls avoids allocating
+    /// a new witness every time.
+    pub fn calculate_witness_into<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        store: &mut Store,
+        inputs: I,
+        sanity_check: bool,
+        out: &mut Vec<BigInt>,
+    ) -> Result<()> {
+        out.clear();
+        out.reserve(self.witness_size(store)? as usize);
+        self.calculate_witness_stream(store, inputs, sanity_check, |_, value| {
+            out.push(value);
+            Ok(())
+        })
+    }
+
+    /// Same as [`calculate_witness`](Self::calculate_witness), but passes every element of
+    /// the witness to `sink` along with its index as soon as it is read out of the wasm,
+    /// instead of collecting them. This bounds the memory needed for very large circuits,
+    /// e.g. when writing the witness to disk. An error returned by `sink` aborts the
+    /// calculation.
+    pub fn calculate_witness_stream<I, S>(
+        &mut self,
+        store: &mut Store,
+        inputs: I,
+        sanity_check: bool,
+        mut sink: S,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, Vec<BigInt>)>,
+        S: FnMut(usize, BigInt) -> Result<()>,
+    {
+        self.calculate_witness_inner(store, inputs, sanity_check, None, &mut sink)
+    }
 
+    /// Same as [`calculate_witness`](Self::calculate_witness), but writes the witness to
+    /// a `.wtns` file at `path`, see [`write_wtns`](crate::write_wtns), e.g. for proving it
+    /// with snarkjs.
+    pub fn calculate_witness_to_file<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        store: &mut Store,
+        inputs: I,
+        path: impl AsRef<std::path::Path>,
+        sanity_check: bool,
+    ) -> Result<()> {
+        let witness = self.calculate_witness(store, inputs, sanity_check)?;
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        super::write_wtns(&mut writer, &self.prime, &witness)?;
+        std::io::Write::flush(&mut writer)?;
+        Ok(())
+    }
+
+    /// Same as [`calculate_witness`](Self::calculate_witness), but only returns the
+    /// witness elements at `indices`, in the same order. The other elements are never read
+    /// out of the wasm, which saves memory when only a few signals are needed.
+    pub fn calculate_selected<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        store: &mut Store,
+        inputs: I,
+        indices: &[usize],
+        sanity_check: bool,
+    ) -> Result<Vec<BigInt>> {
+        let mut witness = Vec::with_capacity(indices.len());
+        self.calculate_witness_inner(
+            store,
+            inputs,
+            sanity_check,
+            Some(indices),
+            &mut |_, value| {
+                witness.push(value);
+                Ok(())
+            },
+        )?;
+        Ok(witness)
+    }
+
+    // Calculates the witness, passing the elements at `indices` to `sink` along with their
+    // index, or all of them if `indices` is `None`
+    fn calculate_witness_inner<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        store: &mut Store,
+        inputs: I,
+        sanity_check: bool,
+        indices: Option<&[usize]>,
+        sink: &mut dyn FnMut(usize, BigInt) -> Result<()>,
+    ) -> Result<()> {
         cfg_if::cfg_if! {
             if #[cfg(feature = "circom-2")] {
                 match self.circom_version {
-                    2 => self.calculate_witness_circom2(store, inputs),
-                    1 => self.calculate_witness_circom1(store, inputs),
+                    2 => self.calculate_witness_circom2(store, inputs, sanity_check, indices, sink),
+                    1 => self.calculate_witness_circom1(store, inputs, sanity_check, indices, sink),
                     _ => panic!("Unknown Circom version")
                 }
             } else {
-                self.calculate_witness_circom1(inputs, sanity_check)
+                self.calculate_witness_circom1(store, inputs, sanity_check, indices, sink)
             }
         }
     }
 
+    /// Same as [`calculate_witness`](Self::calculate_witness), but runs the wasm on
+    /// tokio's blocking thread pool so that large circuits do not stall the runtime. The
+    /// calculator and its store are moved into the blocking task, and handed back along
+    /// with the witness so that they can be reused.
+    #[cfg(feature = "async")]
+    pub async fn calculate_witness_async<I>(
+        mut self,
+        mut store: Store,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<(Vec<BigInt>, Self, Store)>
+    where
+        I: IntoIterator<Item = (String, Vec<BigInt>)> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || {
+            let witness = self.calculate_witness(&mut store, inputs, sanity_check)?;
+            Ok((witness, self, store))
+        })
+        .await?
+    }
+
     // Circom 1 default behavior
     fn calculate_witness_circom1<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
         &mut self,
         store: &mut Store,
         inputs: I,
-    ) -> Result<Vec<BigInt>> {
+        sanity_check: bool,
+        indices: Option<&[usize]>,
+        sink: &mut dyn FnMut(usize, BigInt) -> Result<()>,
+    ) -> Result<()> {
+        self.instance.init(store, sanity_check)?;
+
+        // the allocations of the calculation are freed even if it fails, so that reusing
+        // the calculator does not grow its memory
         let old_mem_free_pos = self.memory.as_ref().unwrap().free_pos(store)?;
+        let result = self.calculate_witness_circom1_allocating(store, inputs, indices, sink);
+        self.memory
+            .as_mut()
+            .unwrap()
+            .set_free_pos(store, old_mem_free_pos)?;
+        result
+    }
+
+    fn calculate_witness_circom1_allocating<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        store: &mut Store,
+        inputs: I,
+        indices: Option<&[usize]>,
+        sink: &mut dyn FnMut(usize, BigInt) -> Result<()>,
+    ) -> Result<()> {
+        let n_vars = self.instance.get_n_vars(store)?;
+        let indices = witness_indices(indices, n_vars)?;
+
         let p_sig_offset = self.memory.as_mut().unwrap().alloc_u32(store)?;
         let p_fr = self.memory.as_mut().unwrap().alloc_fr(store)?;
 
@@ -206,14 +382,24 @@ impl WitnessCalculator {
             let (msb, lsb) = fnv(&name);
 
             self.instance
-                .get_signal_offset32(store, p_sig_offset, 0, msb, lsb)?;
+                .get_signal_offset32(store, p_sig_offset, 0, msb, lsb)
+                .map_err(|err| {
+                    let code = err
+                        .downcast_ref::<RuntimeError>()
+                        .and_then(|err| err.downcast_ref::<CircomError>())
+                        .map(|err| err.code);
+                    if code == Some(HASH_NOT_FOUND) {
+                        err.wrap_err(WitnessError::UnknownInput(name.clone()))
+                    } else {
+                        err
+                    }
+                })?;
 
             let sig_offset = self
                 .memory
                 .as_ref()
                 .unwrap()
-                .read_u32(store, p_sig_offset as usize)
-                .unwrap() as usize;
+                .read_u32(store, p_sig_offset as usize)? as usize;
 
             for (i, value) in values.into_iter().enumerate() {
                 self.memory
@@ -225,63 +411,33 @@ impl WitnessCalculator {
             }
         }
 
-        let mut w = Vec::new();
-
-        let n_vars = self.instance.get_n_vars(store)?;
-        for i in 0..n_vars {
+        for i in indices {
             let ptr = self.instance.get_ptr_witness(store, i)? as usize;
             let el = self.memory.as_ref().unwrap().read_fr(store, ptr)?;
-            w.push(el);
+            sink(i as usize, el)?;
         }
 
-        self.memory
-            .as_mut()
-            .unwrap()
-            .set_free_pos(store, old_mem_free_pos)?;
-
-        Ok(w)
+        Ok(())
     }
 
-    // Circom 2 feature flag with version 2
+    // Circom 2 feature flag with version 2, run through the same `CircomRuntime` functions
+    // as any other engine
     #[cfg(feature = "circom-2")]
     fn calculate_witness_circom2<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
         &mut self,
         store: &mut Store,
         inputs: I,
-    ) -> Result<Vec<BigInt>> {
-        let n32 = self.instance.get_field_num_len32(store)?;
-
-        // allocate the inputs
-        for (name, values) in inputs.into_iter() {
-            let (msb, lsb) = fnv(&name);
-
-            for (i, value) in values.into_iter().enumerate() {
-                let f_arr = to_array32(&value, n32 as usize);
-                for j in 0..n32 {
-                    self.instance.write_shared_rw_memory(
-                        store,
-                        j,
-                        f_arr[(n32 as usize) - 1 - (j as usize)],
-                    )?;
-                }
-                self.instance.set_input_signal(store, msb, lsb, i as u32)?;
-            }
-        }
-
-        let mut w = Vec::new();
-
-        let witness_size = self.instance.get_witness_size(store)?;
-        for i in 0..witness_size {
-            self.instance.get_witness(store, i)?;
-            let mut arr = vec![0; n32 as usize];
-            for j in 0..n32 {
-                arr[(n32 as usize) - 1 - (j as usize)] =
-                    self.instance.read_shared_rw_memory(store, j)?;
-            }
-            w.push(from_array32(arr));
-        }
-
-        Ok(w)
+        sanity_check: bool,
+        indices: Option<&[usize]>,
+        sink: &mut dyn FnMut(usize, BigInt) -> Result<()>,
+    ) -> Result<()> {
+        let mut runtime = WasmerRuntime::new(&self.instance, store);
+        runtime.init(sanity_check)?;
+        let witness_size = runtime.get_witness_size()?;
+        let indices = witness_indices(indices, witness_size)?;
+
+        let n32 = set_inputs_circom2(&mut runtime, &self.prime, inputs)?;
+        read_witness_circom2(&mut runtime, n32, indices, sink)
     }
 
     pub fn calculate_witness_element<
@@ -293,82 +449,447 @@ impl WitnessCalculator {
         inputs: I,
         sanity_check: bool,
     ) -> Result<Vec<F>> {
-        let modulus = F::MODULUS;
+        self.check_field::<F>()?;
+
+        #[cfg(feature = "circom-2")]
+        if self.circom_version == 2 {
+            let mut runtime = WasmerRuntime::new(&self.instance, store);
+            runtime.init(sanity_check)?;
+            let n32 = set_inputs_circom2(&mut runtime, &self.prime, inputs)?;
+            let witness_size = runtime.get_witness_size()?;
+            return read_witness_elements_circom2(&mut runtime, n32, 0..witness_size);
+        }
+
         let witness = self.calculate_witness(store, inputs, sanity_check)?;
 
         // convert it to field elements
-        use num_traits::Signed;
         let witness = witness
-            .into_iter()
-            .map(|w| {
-                let w = if w.sign() == num_bigint::Sign::Minus {
-                    // Need to negate the witness element if negative
-                    modulus.into() - w.abs().to_biguint().unwrap()
-                } else {
-                    w.to_biguint().unwrap()
-                };
-                F::from(w)
-            })
+            .iter()
+            .map(crate::circom::bigint_to_field)
             .collect::<Vec<_>>();
 
         Ok(witness)
     }
+
+    /// Returns whether the circuit is defined over the field `F`, i.e. whether its prime is
+    /// the modulus of `F`
+    pub fn matches_field<F: PrimeField>(&self) -> bool {
+        let modulus: BigUint = F::MODULUS.into();
+        self.prime == BigInt::from(modulus)
+    }
+
+    // converting the witness to the wrong field would silently reduce it modulo its prime
+    fn check_field<F: PrimeField>(&self) -> Result<()> {
+        if !self.matches_field::<F>() {
+            return Err(eyre!(
+                "the circuit is for the field of prime {}, which is not the requested field",
+                self.prime
+            ));
+        }
+        Ok(())
+    }
+
+    /// Calculates the witness for the provided inputs, but only returns the `num_public`
+    /// public signals following the constant 1, i.e. the outputs and then the public inputs
+    /// of the circuit, as expected by the verifier. For Circom 2 circuits the rest of the
+    /// witness is never read out of the wasm.
+    pub fn calculate_public_inputs<F: PrimeField, I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        store: &mut Store,
+        inputs: I,
+        num_public: usize,
+        sanity_check: bool,
+    ) -> Result<Vec<F>> {
+        self.check_field::<F>()?;
+
+        #[cfg(feature = "circom-2")]
+        if self.circom_version == 2 {
+            let mut runtime = WasmerRuntime::new(&self.instance, store);
+            runtime.init(sanity_check)?;
+            let n32 = set_inputs_circom2(&mut runtime, &self.prime, inputs)?;
+            let witness_size = runtime.get_witness_size()?;
+            return match u32::try_from(num_public) {
+                Ok(num_public) if num_public < witness_size => {
+                    read_witness_elements_circom2(&mut runtime, n32, 1..num_public + 1)
+                }
+                _ => Err(eyre!(
+                    "the witness has {witness_size} elements, which cannot hold {num_public} public signals"
+                )),
+            };
+        }
+
+        let mut witness = self.calculate_witness_element(store, inputs, sanity_check)?;
+        if num_public >= witness.len() {
+            return Err(eyre!(
+                "the witness has {} elements, which cannot hold {num_public} public signals",
+                witness.len()
+            ));
+        }
+        witness.truncate(num_public + 1);
+        witness.remove(0);
+        Ok(witness)
+    }
+
+    /// Forwards the output of the circuit to `sink`, one line at a time and formatted like
+    /// snarkjs prints it: the `log()` calls of Circom 2 circuits and their `printDebug`
+    /// output, or for Circom 1 circuits their `log()` calls and the signals and components
+    /// they trace, e.g. `SET 3 <-- 33`, with indices in place of the names of the symbol
+    /// file. Logs are discarded until a sink is set.
+    pub fn set_log_sink(
+        &mut self,
+        store: &mut Store,
+        sink: impl FnMut(String) + Send + 'static,
+    ) -> Result<()> {
+        let env = self
+            .instance
+            .runtime_env
+            .as_ref()
+            .ok_or_else(|| eyre!("the wasm runtime was not created by `make_wasm_runtime`"))?;
+        env.as_mut(store).log = Some(Box::new(sink));
+        Ok(())
+    }
+
+    /// Resets the state left in the wasm memory by a previous calculation, e.g. one which
+    /// failed halfway, without instantiating the module again. For Circom 2 the shared
+    /// memory used to pass field elements is zeroed, and for Circom 1 the allocator is
+    /// reset to its initial position.
+    pub fn soft_reset(&mut self, store: &mut Store) -> Result<()> {
+        if let Some(memory) = &self.memory {
+            memory.set_free_pos(store, memory.initial_free_pos)?;
+        }
+
+        #[cfg(feature = "circom-2")]
+        if self.circom_version == 2 {
+            let mut runtime = WasmerRuntime::new(&self.instance, store);
+            let n32 = runtime.get_field_num_len32()?;
+            for i in 0..n32 {
+                runtime.write_shared_rw_memory(i, 0)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of elements of the witnesses calculated by the circuit, i.e. the
+    /// number of wires of its r1cs. It does not depend on the inputs, so it can be used to
+    /// allocate the output of [`calculate_witness_stream`](Self::calculate_witness_stream)
+    /// before calculating anything.
+    pub fn witness_size(&self, store: &mut Store) -> Result<u32> {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "circom-2")] {
+                match self.circom_version {
+                    2 => WasmerRuntime::new(&self.instance, store).get_witness_size(),
+                    1 => self.instance.get_n_vars(store),
+                    _ => panic!("Unknown Circom version")
+                }
+            } else {
+                self.instance.get_n_vars(store)
+            }
+        }
+    }
+
+    /// Returns the number of wasm pages currently allocated to the runtime's memory.
+    ///
+    /// Memory only ever grows, so calling this after `calculate_witness` gives the peak
+    /// number of pages used by the circuit for the provided inputs.
+    pub fn last_memory_pages(&self, store: &mut Store) -> u32 {
+        self.instance.memory.view(store).size().0
+    }
 }
 
+// Returns the indices of the witness elements to read, checking that they are in bounds
+fn witness_indices(
+    indices: Option<&[usize]>,
+    witness_size: u32,
+) -> Result<Box<dyn ExactSizeIterator<Item = u32> + '_>> {
+    match indices {
+        None => Ok(Box::new(0..witness_size)),
+        Some(indices) => {
+            if let Some(index) = indices.iter().find(|&&i| i >= witness_size as usize) {
+                return Err(eyre!(
+                    "witness index {index} is out of bounds, the witness has {witness_size} elements"
+                ));
+            }
+            Ok(Box::new(indices.iter().map(|&i| i as u32)))
+        }
+    }
+}
+
+pub(super) use runtime::RuntimeEnv;
+
 // callback hooks for debugging
 mod runtime {
     use super::*;
 
-    pub fn error(store: &mut Store) -> Function {
+    pub type LogSink = Box<dyn FnMut(String) + Send>;
+
+    /// State shared by the callbacks which need to read from the wasm instance
+    pub struct RuntimeEnv {
+        memory: Memory,
+        pub exports: Option<Exports>,
+        // error messages reported by Circom 2 before raising an exception
+        error_message: String,
+        pub log: Option<LogSink>,
+        // the line of `log()` output being assembled
+        log_buffer: String,
+        // used to read the field elements passed to the Circom 1 callbacks
+        pub fr_memory: Option<super::SafeMemory>,
+    }
+
+    impl std::fmt::Debug for RuntimeEnv {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("RuntimeEnv")
+                .field("error_message", &self.error_message)
+                .field("log", &self.log.is_some())
+                .finish()
+        }
+    }
+
+    impl RuntimeEnv {
+        pub fn new(memory: Memory) -> Self {
+            Self {
+                memory,
+                exports: None,
+                error_message: String::new(),
+                log: None,
+                log_buffer: String::new(),
+                fr_memory: None,
+            }
+        }
+    }
+
+    fn call_export(
+        env: &mut FunctionEnvMut<RuntimeEnv>,
+        name: &str,
+        params: &[Value],
+    ) -> Option<Box<[Value]>> {
+        let func = env
+            .data()
+            .exports
+            .as_ref()?
+            .get_function(name)
+            .ok()?
+            .clone();
+        func.call(env, params).ok()
+    }
+
+    fn call_export_i32(env: &mut FunctionEnvMut<RuntimeEnv>, name: &str, params: &[Value]) -> i32 {
+        call_export(env, name, params)
+            .and_then(|result| result.first().and_then(Value::i32))
+            .unwrap_or(0)
+    }
+
+    // Reads a null-terminated string from the wasm memory
+    fn read_string(env: &FunctionEnvMut<RuntimeEnv>, ptr: i32) -> String {
+        let view = env.data().memory.view(env);
+        let mut bytes = Vec::new();
+        let mut byte = [0u8];
+        let mut offset = ptr as u32 as u64;
+        while view.read(offset, &mut byte).is_ok() && byte[0] != 0 {
+            bytes.push(byte[0]);
+            offset += 1;
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    // Reads the current message of a Circom 2 runtime, one character at a time
+    fn read_message(env: &mut FunctionEnvMut<RuntimeEnv>) -> String {
+        let mut bytes = Vec::new();
+        loop {
+            match call_export_i32(env, "getMessageChar", &[]) {
+                0 => break,
+                c => bytes.push(c as u8),
+            }
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    // Passes a line to the log sink, only formatting it if a sink is set
+    fn send_log(
+        env: &mut FunctionEnvMut<RuntimeEnv>,
+        line: impl FnOnce(&FunctionEnvMut<RuntimeEnv>) -> String,
+    ) {
+        if env.data().log.is_none() {
+            return;
+        }
+        let line = line(env);
+        if let Some(log) = env.data_mut().log.as_mut() {
+            log(line);
+        }
+    }
+
+    // Appends an item to the current line of `log()` output, separated by a space
+    fn push_log(env: &mut FunctionEnvMut<RuntimeEnv>, item: &str) {
+        let buffer = &mut env.data_mut().log_buffer;
+        if !buffer.is_empty() {
+            buffer.push(' ');
+        }
+        buffer.push_str(item);
+    }
+
+    pub fn error(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
         #[allow(unused)]
         #[allow(clippy::many_single_char_names)]
-        fn func(a: i32, b: i32, c: i32, d: i32, e: i32, f: i32) -> Result<(), RuntimeError> {
-            // NOTE: We can also get more information why it is failing, see p2str etc here:
+        fn func(
+            env: FunctionEnvMut<RuntimeEnv>,
+            code: i32,
+            pstr: i32,
+            a: i32,
+            b: i32,
+            c: i32,
+            d: i32,
+        ) -> Result<(), RuntimeError> {
             // https://github.com/iden3/circom_runtime/blob/master/js/witness_calculator.js#L52-L64
-            println!("runtime error, exiting early: {a} {b} {c} {d} {e} {f}",);
-            Err(RuntimeError::user(Box::new(ExitCode(1))))
+            // For code 5, `c` indexes the symbol table of the circuit, which is not loaded here
+            let message = match code {
+                7 => format!(
+                    "{} {} != {} {}",
+                    read_string(&env, pstr),
+                    read_fr(&env, b),
+                    read_fr(&env, c),
+                    read_string(&env, d)
+                ),
+                9 => format!(
+                    "{} {} {}",
+                    read_string(&env, pstr),
+                    read_fr(&env, b),
+                    read_string(&env, c)
+                ),
+                _ => read_string(&env, pstr),
+            };
+            Err(RuntimeError::user(Box::new(CircomError {
+                code,
+                message: format!("error {code}: {message}"),
+            })))
         }
-        Function::new_typed(store, func)
+        Function::new_typed_with_env(store, env, func)
     }
 
     // Circom 2.0
-    pub fn exception_handler(store: &mut Store) -> Function {
-        #[allow(unused)]
-        fn func(a: i32) {}
-        Function::new_typed(store, func)
+    pub fn exception_handler(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>, code: i32) -> Result<(), RuntimeError> {
+            let message = std::mem::take(&mut env.data_mut().error_message);
+            Err(RuntimeError::user(Box::new(CircomError::exception(
+                code, &message,
+            ))))
+        }
+        Function::new_typed_with_env(store, env, func)
     }
 
-    // Circom 2.0
-    pub fn show_memory(store: &mut Store) -> Function {
-        #[allow(unused)]
-        fn func() {}
-        Function::new_typed(store, func)
+    // Circom 2.0, logs the field element in the shared memory
+    pub fn show_memory(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>) {
+            if env.data().log.is_none() {
+                return;
+            }
+            let n32 = call_export_i32(&mut env, "getFieldNumLen32", &[]);
+            let limbs = (0..n32)
+                .map(|j| call_export_i32(&mut env, "readSharedRWMemory", &[j.into()]) as u32)
+                .collect::<Vec<_>>();
+            push_log(&mut env, &BigUint::from_slice(&limbs).to_string());
+        }
+        Function::new_typed_with_env(store, env, func)
     }
 
     // Circom 2.0
-    pub fn print_error_message(store: &mut Store) -> Function {
-        #[allow(unused)]
-        fn func() {}
-        Function::new_typed(store, func)
+    pub fn print_error_message(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>) {
+            let message = read_message(&mut env);
+            let error_message = &mut env.data_mut().error_message;
+            error_message.push_str(&message);
+            error_message.push('\n');
+        }
+        Function::new_typed_with_env(store, env, func)
     }
 
-    // Circom 2.0
-    pub fn write_buffer_message(store: &mut Store) -> Function {
-        #[allow(unused)]
-        fn func() {}
-        Function::new_typed(store, func)
+    // Circom 2.0, logs the current message. Every `log()` call ends with a newline message.
+    pub fn write_buffer_message(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>) {
+            if env.data().log.is_none() {
+                return;
+            }
+            let message = read_message(&mut env);
+            if message == "\n" {
+                let line = std::mem::take(&mut env.data_mut().log_buffer);
+                send_log(&mut env, |_| line);
+            } else {
+                push_log(&mut env, &message);
+            }
+        }
+        Function::new_typed_with_env(store, env, func)
     }
 
-    pub fn log_signal(store: &mut Store) -> Function {
-        #[allow(unused)]
-        fn func(a: i32, b: i32) {}
-        Function::new_typed(store, func)
+    // Circom 2.2, logged like circom_runtime does
+    pub fn print_debug(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>, value: i32) {
+            send_log(&mut env, |_| format!("printDebug: {value}"));
+        }
+        Function::new_typed_with_env(store, env, func)
     }
 
-    pub fn log_component(store: &mut Store) -> Function {
-        #[allow(unused)]
-        fn func(a: i32) {}
-        Function::new_typed(store, func)
+    // Circom 1.0, logged like `snarkjs wtns debug` does, with the index of the signal in
+    // place of its name. Also traced when the `tracing` feature is enabled.
+    pub fn log_set_signal(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>, signal: i32, p_val: i32) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(signal, value = %read_fr(&env, p_val), "set signal");
+            send_log(&mut env, |env| {
+                format!("SET {signal} <-- {}", read_fr(env, p_val))
+            });
+        }
+        Function::new_typed_with_env(store, env, func)
+    }
+
+    // Circom 1.0, see `log_set_signal`
+    pub fn log_get_signal(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>, signal: i32, p_val: i32) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(signal, value = %read_fr(&env, p_val), "get signal");
+            send_log(&mut env, |env| {
+                format!("GET {signal} --> {}", read_fr(env, p_val))
+            });
+        }
+        Function::new_typed_with_env(store, env, func)
+    }
+
+    // Circom 1.0, logged with the index of the component in place of its name
+    pub fn log_start_component(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>, component: i32) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(component, "start component");
+            send_log(&mut env, |_| format!("START: {component}"));
+        }
+        Function::new_typed_with_env(store, env, func)
+    }
+
+    // Circom 1.0, see `log_start_component`
+    pub fn log_finish_component(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>, component: i32) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(component, "finish component");
+            send_log(&mut env, |_| format!("FINISH: {component}"));
+        }
+        Function::new_typed_with_env(store, env, func)
+    }
+
+    // Circom 1.0, the value passed to `log()` in the circuit
+    pub fn log(store: &mut Store, env: &FunctionEnv<RuntimeEnv>) -> Function {
+        fn func(mut env: FunctionEnvMut<RuntimeEnv>, value: i32) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(value, "log");
+            send_log(&mut env, |_| value.to_string());
+        }
+        Function::new_typed_with_env(store, env, func)
+    }
+
+    // Reads a field element passed to a Circom 1 callback, which is only possible once the
+    // prime is known
+    fn read_fr(env: &FunctionEnvMut<RuntimeEnv>, ptr: i32) -> String {
+        env.data()
+            .fr_memory
+            .as_ref()
+            .and_then(|memory| memory.read_fr(env, ptr as u32 as usize).ok())
+            .map_or_else(|| "?".to_string(), |value| value.to_string())
     }
 }
 
@@ -465,12 +986,600 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn memory_pages() {
+        let mut store = Store::default();
+        let mut wtns =
+            WitnessCalculator::new(&mut store, root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ]);
+        wtns.calculate_witness(&mut store, inputs, false).unwrap();
+        assert!(wtns.last_memory_pages(&mut store) >= 2000);
+    }
+
+    #[tokio::test]
+    async fn custom_memory_pages() {
+        let mut store = Store::default();
+        let module = Module::from_file(&store, root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let mut wtns =
+            WitnessCalculator::from_module_with_memory_pages(&mut store, module, 4000).unwrap();
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ]);
+        let witness = wtns.calculate_witness(&mut store, inputs, false).unwrap();
+        assert_eq!(witness[1], BigInt::from(33));
+        assert!(wtns.last_memory_pages(&mut store) >= 4000);
+    }
+
+    #[tokio::test]
+    async fn from_bytes() {
+        let mut store = Store::default();
+        let bytes = std::fs::read(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let mut wtns = WitnessCalculator::from_bytes(&mut store, bytes).unwrap();
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ]);
+        let res = wtns.calculate_witness(&mut store, inputs, false).unwrap();
+        assert_eq!(res[1], BigInt::from(33));
+    }
+
+    #[test]
+    fn soft_reset() {
+        for path in [
+            "test-vectors/mycircuit.wasm",
+            "test-vectors/circom2_multiplier2.wasm",
+        ] {
+            let mut store = Store::default();
+            let mut wtns = WitnessCalculator::new(&mut store, root_path(path)).unwrap();
+            for (a, b) in [(3, 11), (5, 7)] {
+                let inputs = HashMap::from([
+                    ("a".to_string(), vec![BigInt::from(a)]),
+                    ("b".to_string(), vec![BigInt::from(b)]),
+                ]);
+                let witness = wtns.calculate_witness(&mut store, inputs, false).unwrap();
+                assert_eq!(witness[1], BigInt::from(a * b));
+                wtns.soft_reset(&mut store).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn calculate_selected() {
+        for path in [
+            "test-vectors/mycircuit.wasm",
+            "test-vectors/circom2_multiplier2.wasm",
+        ] {
+            let mut store = Store::default();
+            let mut wtns = WitnessCalculator::new(&mut store, root_path(path)).unwrap();
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![BigInt::from(3)]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]);
+            let witness = wtns
+                .calculate_witness(&mut store, inputs.clone(), false)
+                .unwrap();
+
+            let selected = wtns
+                .calculate_selected(&mut store, inputs.clone(), &[3, 1], false)
+                .unwrap();
+            assert_eq!(selected, vec![witness[3].clone(), witness[1].clone()]);
+
+            assert!(wtns
+                .calculate_selected(&mut store, inputs, &[4], false)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn calculate_witness_stream() {
+        let mut store = Store::default();
+        let mut wtns = WitnessCalculator::new(
+            &mut store,
+            root_path("test-vectors/circom2_multiplier2.wasm"),
+        )
+        .unwrap();
+        let inputs = || {
+            [
+                ("a".to_string(), vec![BigInt::from(3)]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]
+        };
+
+        let mut streamed = Vec::new();
+        wtns.calculate_witness_stream(&mut store, inputs(), false, |i, value| {
+            assert_eq!(i, streamed.len());
+            streamed.push(value);
+            Ok(())
+        })
+        .unwrap();
+        let witness = wtns.calculate_witness(&mut store, inputs(), false).unwrap();
+        assert_eq!(streamed, witness);
+
+        // the sink can stop the calculation
+        let mut count = 0;
+        let err = wtns.calculate_witness_stream(&mut store, inputs(), false, |_, _| {
+            count += 1;
+            Err(eyre!("stop"))
+        });
+        assert!(err.is_err());
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn witness_size() {
+        let mut store = Store::default();
+        let mut wtns = WitnessCalculator::new(
+            &mut store,
+            root_path("test-vectors/circom2_multiplier2.wasm"),
+        )
+        .unwrap();
+        let size = wtns.witness_size(&mut store).unwrap() as usize;
+
+        let mut witness = Vec::with_capacity(size);
+        let inputs = [
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ];
+        wtns.calculate_witness_stream(&mut store, inputs, false, |_, value| {
+            witness.push(value);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(witness.len(), size);
+    }
+
+    #[test]
+    fn calculate_witness_into() {
+        let mut store = Store::default();
+        let mut wtns = WitnessCalculator::new(
+            &mut store,
+            root_path("test-vectors/circom2_multiplier2.wasm"),
+        )
+        .unwrap();
+
+        let mut witness = vec![BigInt::from(42); 10];
+        for (a, b) in [(3, 11), (5, 7)] {
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![BigInt::from(a)]),
+                ("b".to_string(), vec![BigInt::from(b)]),
+            ]);
+            wtns.calculate_witness_into(&mut store, inputs, false, &mut witness)
+                .unwrap();
+            assert_eq!(witness.len(), 4);
+            assert_eq!(witness[1], BigInt::from(a * b));
+        }
+    }
+
+    #[test]
+    fn calculate_public_inputs() {
+        let mut store = Store::default();
+        let mut wtns = WitnessCalculator::new(
+            &mut store,
+            root_path("test-vectors/circom2_multiplier2.wasm"),
+        )
+        .unwrap();
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ]);
+
+        let public = wtns
+            .calculate_public_inputs::<ark_bn254::Fr, _>(&mut store, inputs.clone(), 1, false)
+            .unwrap();
+        assert_eq!(public, vec![ark_bn254::Fr::from(33)]);
+
+        assert!(wtns
+            .calculate_public_inputs::<ark_bn254::Fr, _>(&mut store, inputs, 4, false)
+            .is_err());
+    }
+
+    #[test]
+    fn witness_to_file() {
+        let mut store = Store::default();
+        let mut wtns =
+            WitnessCalculator::new(&mut store, root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let path = std::env::temp_dir().join("ark-circom-mycircuit.wtns");
+        let inputs = [
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ];
+        wtns.calculate_witness_to_file(&mut store, inputs, &path, false)
+            .unwrap();
+
+        let mut expected = Vec::new();
+        let witness = [1, 33, 3, 11].map(BigInt::from);
+        crate::write_wtns(&mut expected, &wtns.prime, &witness).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), expected);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn repeated_calculations() {
+        for path in ["mycircuit.wasm", "circom2_multiplier2.wasm"] {
+            let mut store = Store::default();
+            let mut wtns =
+                WitnessCalculator::new(&mut store, root_path(&format!("test-vectors/{path}")))
+                    .unwrap();
+            let inputs = || {
+                [
+                    ("a".to_string(), vec![BigInt::from(3)]),
+                    ("b".to_string(), vec![BigInt::from(11)]),
+                ]
+            };
+            wtns.calculate_witness(&mut store, inputs(), false).unwrap();
+            let pages = wtns.last_memory_pages(&mut store);
+            let free_pos = wtns
+                .memory
+                .as_ref()
+                .map(|m| m.free_pos(&mut store).unwrap());
+
+            for _ in 0..1000 {
+                // failed calculations must not leak their allocations either
+                let _ = wtns.calculate_witness(&mut store, [("c".to_string(), vec![])], false);
+                wtns.calculate_witness(&mut store, inputs(), false).unwrap();
+            }
+            assert_eq!(wtns.last_memory_pages(&mut store), pages);
+            assert_eq!(
+                wtns.memory
+                    .as_ref()
+                    .map(|m| m.free_pos(&mut store).unwrap()),
+                free_pos
+            );
+        }
+    }
+
+    #[test]
+    fn negative_inputs() {
+        let mut store = Store::default();
+        let mut wtns = WitnessCalculator::new(
+            &mut store,
+            root_path("test-vectors/circom2_multiplier2.wasm"),
+        )
+        .unwrap();
+        let inputs = |a: BigInt| {
+            [
+                ("a".to_string(), vec![a]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]
+        };
+
+        // -1 and -p - 1 are both p - 1
+        let expected = wtns
+            .calculate_witness(&mut store, inputs(&wtns.prime - 1), false)
+            .unwrap();
+        for a in [BigInt::from(-1), -&wtns.prime - 1] {
+            let witness = wtns
+                .calculate_witness(&mut store, inputs(a), false)
+                .unwrap();
+            assert_eq!(witness, expected);
+        }
+    }
+
+    #[test]
+    fn mismatched_field() {
+        let mut store = Store::default();
+        let mut wtns = WitnessCalculator::new(
+            &mut store,
+            root_path("test-vectors/circom2_multiplier2.wasm"),
+        )
+        .unwrap();
+        assert!(wtns.matches_field::<ark_bn254::Fr>());
+        assert!(!wtns.matches_field::<ark_bls12_381::Fr>());
+
+        let inputs = [
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ];
+        assert!(wtns
+            .calculate_witness_element::<ark_bls12_381::Fr, _>(&mut store, inputs, false)
+            .is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn calculate_witness_async() {
+        let mut store = Store::default();
+        let wtns =
+            WitnessCalculator::new(&mut store, root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ]);
+        let (witness, wtns, store) = wtns
+            .calculate_witness_async(store, inputs.clone(), false)
+            .await
+            .unwrap();
+        assert_eq!(witness[1], BigInt::from(33));
+
+        // the calculator can be reused
+        let (witness, _, _) = wtns
+            .calculate_witness_async(store, inputs, false)
+            .await
+            .unwrap();
+        assert_eq!(witness[1], BigInt::from(33));
+    }
+
+    #[tokio::test]
+    async fn witness_element_circom2() {
+        let mut store = Store::default();
+        let mut wtns = WitnessCalculator::new(
+            &mut store,
+            root_path("test-vectors/circom2_multiplier2.wasm"),
+        )
+        .unwrap();
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ]);
+        let witness = wtns
+            .calculate_witness(&mut store, inputs.clone(), false)
+            .unwrap();
+        let elements = wtns
+            .calculate_witness_element::<ark_bn254::Fr, _>(&mut store, inputs, false)
+            .unwrap();
+        assert_eq!(
+            elements,
+            witness
+                .into_iter()
+                .map(|w| ark_bn254::Fr::from(w.to_biguint().unwrap()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(elements[1], ark_bn254::Fr::from(33));
+    }
+
+    #[tokio::test]
+    async fn input_names() {
+        // smtverifier10 was compiled by circom, and takes `siblings[10]` which it passes on
+        // to its sub-components
+        let mut store = Store::default();
+        let mut wtns =
+            WitnessCalculator::new(&mut store, root_path("test-vectors/smtverifier10.wasm"))
+                .unwrap();
+        let inputs = read_inputs(&root_path("test-vectors/smtverifier10-input.json"));
+
+        // the elements of `siblings` are passed under its base name, in order
+        let witness = wtns
+            .calculate_witness(&mut store, inputs.clone(), false)
+            .unwrap();
+        let expected =
+            std::fs::read_to_string(root_path("test-vectors/smtverifier10-witness.json")).unwrap();
+        let expected: Vec<String> = serde_json::from_str(&expected).unwrap();
+        assert_eq!(
+            witness,
+            expected
+                .iter()
+                .map(|w| BigInt::from_str(w).unwrap())
+                .collect::<Vec<_>>()
+        );
+
+        // neither indexed names nor the dotted names of the main component are signals
+        for name in ["siblings[0]", "main.siblings"] {
+            let mut inputs = inputs.clone();
+            let siblings = inputs.remove("siblings").unwrap();
+            inputs.insert(name.to_string(), siblings);
+            let err = wtns
+                .calculate_witness(&mut store, inputs, false)
+                .unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<WitnessError>(),
+                Some(&WitnessError::UnknownInput(name.to_string()))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_input_circom1() {
+        let mut store = Store::default();
+        let mut wtns =
+            WitnessCalculator::new(&mut store, root_path("test-vectors/mycircuit.wasm")).unwrap();
+        assert_eq!(wtns.circom_version, 1);
+        let inputs = HashMap::from([("nonexistent".to_string(), vec![BigInt::from(5)])]);
+        let err = wtns
+            .calculate_witness(&mut store, inputs, false)
+            .unwrap_err();
+        assert!(err
+            .chain()
+            .any(|err| err.to_string().contains("Hash not found")));
+        assert_eq!(
+            err.downcast_ref::<WitnessError>(),
+            Some(&WitnessError::UnknownInput("nonexistent".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn runtime_error_message() {
+        let mut store = Store::default();
+        let mut wtns = WitnessCalculator::new(
+            &mut store,
+            root_path("test-vectors/circom2_multiplier2.wasm"),
+        )
+        .unwrap();
+        let inputs = HashMap::from([("foo".to_string(), vec![BigInt::from(3)])]);
+        let err = wtns
+            .calculate_witness(&mut store, inputs, false)
+            .unwrap_err();
+        assert!(err
+            .chain()
+            .any(|err| err.to_string().contains("Signal not found.")));
+        assert_eq!(
+            err.downcast_ref::<WitnessError>(),
+            Some(&WitnessError::UnknownInput("foo".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn log_sink() {
+        let mut store = Store::default();
+        // logs `x = 2^32 + 5` the way circom 2 compiles `log("x =", x)`
+        let module = Module::new(
+            &store,
+            r#"(module
+                (import "env" "memory" (memory 1))
+                (import "runtime" "writeBufferMessage" (func $write))
+                (import "runtime" "showSharedRWMemory" (func $show))
+                (data (i32.const 0) "x =\00")
+                (data (i32.const 8) "\n\00")
+                (global $pos (mut i32) (i32.const 0))
+                (func (export "getMessageChar") (result i32)
+                    (local $c i32)
+                    (local.set $c (i32.load8_u (global.get $pos)))
+                    (if (local.get $c)
+                        (then (global.set $pos (i32.add (global.get $pos) (i32.const 1)))))
+                    (local.get $c))
+                (func (export "getFieldNumLen32") (result i32) (i32.const 2))
+                (func (export "readSharedRWMemory") (param i32) (result i32)
+                    (select (i32.const 5) (i32.const 1) (i32.eqz (local.get 0))))
+                (func (export "run")
+                    (global.set $pos (i32.const 0))
+                    (call $write)
+                    (call $show)
+                    (global.set $pos (i32.const 8))
+                    (call $write)))"#,
+        )
+        .unwrap();
+        let wasm = WitnessCalculator::make_wasm_runtime(&mut store, module).unwrap();
+        let run = wasm.exports.get_function("run").unwrap().clone();
+
+        // silent by default
+        run.call(&mut store, &[]).unwrap();
+
+        let lines = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        wasm.runtime_env.as_ref().unwrap().as_mut(&mut store).log =
+            Some(Box::new(move |line| sink.lock().unwrap().push(line)));
+        run.call(&mut store, &[]).unwrap();
+        assert_eq!(*lines.lock().unwrap(), vec!["x = 4294967301".to_string()]);
+
+        // the traces of Circom 1 and the `printDebug` output of Circom 2.2
+        let module = Module::new(
+            &store,
+            r#"(module
+                (import "env" "memory" (memory 1))
+                (import "runtime" "logStartComponent" (func $start (param i32)))
+                (import "runtime" "logGetSignal" (func $get (param i32 i32)))
+                (import "runtime" "logSetSignal" (func $set (param i32 i32)))
+                (import "runtime" "log" (func $log (param i32)))
+                (import "runtime" "logFinishComponent" (func $finish (param i32)))
+                (import "runtime" "printDebug" (func $debug (param i32)))
+                (func (export "run")
+                    (call $start (i32.const 1))
+                    (call $get (i32.const 3) (i32.const 64))
+                    (call $set (i32.const 1) (i32.const 64))
+                    (call $log (i32.const 7))
+                    (call $finish (i32.const 1))
+                    (call $debug (i32.const 2))))"#,
+        )
+        .unwrap();
+        let wasm = WitnessCalculator::make_wasm_runtime(&mut store, module).unwrap();
+        let run = wasm.exports.get_function("run").unwrap().clone();
+        let prime = BigInt::from_str(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+        )
+        .unwrap();
+        let memory = SafeMemory::new(wasm.memory.clone(), 8, prime);
+        memory.write_fr(&mut store, 64, &BigInt::from(33)).unwrap();
+        let env = wasm.runtime_env.as_ref().unwrap().as_mut(&mut store);
+        env.fr_memory = Some(memory);
+        let sink = lines.clone();
+        env.log = Some(Box::new(move |line| sink.lock().unwrap().push(line)));
+
+        lines.lock().unwrap().clear();
+        run.call(&mut store, &[]).unwrap();
+        assert_eq!(
+            *lines.lock().unwrap(),
+            [
+                "START: 1",
+                "GET 3 --> 33",
+                "SET 1 <-- 33",
+                "7",
+                "FINISH: 1",
+                "printDebug: 2"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn circom1_error_messages() {
+        let mut store = Store::default();
+        let module = Module::new(
+            &store,
+            r#"(module
+                (import "env" "memory" (memory 1))
+                (import "runtime" "error"
+                    (func $error (param i32 i32 i32 i32 i32 i32)))
+                (data (i32.const 0) "Constraint doesn't match\00")
+                (data (i32.const 32) "main.out\00")
+                (func (export "fail") (param i32 i32 i32 i32 i32 i32)
+                    (call $error (local.get 0) (local.get 1) (local.get 2)
+                        (local.get 3) (local.get 4) (local.get 5))))"#,
+        )
+        .unwrap();
+        let wasm = WitnessCalculator::make_wasm_runtime(&mut store, module).unwrap();
+        let fail = wasm.exports.get_function("fail").unwrap().clone();
+
+        let prime = BigInt::from_str(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+        )
+        .unwrap();
+        let memory = SafeMemory::new(wasm.memory.clone(), 8, prime);
+        memory.write_fr(&mut store, 64, &BigInt::from(33)).unwrap();
+        memory.write_fr(&mut store, 128, &BigInt::from(-1)).unwrap();
+        wasm.runtime_env
+            .as_ref()
+            .unwrap()
+            .as_mut(&mut store)
+            .fr_memory = Some(memory);
+
+        let mut message = |code: i32, c: i32, d: i32| {
+            let params = [code, 0, 0, 64, c, d].map(wasmer::Value::I32);
+            let err = fail.call(&mut store, &params).unwrap_err();
+            let err = err.downcast::<CircomError>().unwrap();
+            assert_eq!(err.code, code);
+            err.message
+        };
+        assert_eq!(
+            message(7, 128, 32),
+            "error 7: Constraint doesn't match 33 != -1 main.out"
+        );
+        assert_eq!(
+            message(9, 32, 0),
+            "error 9: Constraint doesn't match 33 main.out"
+        );
+        // the symbol index of code 5 is left out
+        assert_eq!(message(5, 3, 0), "error 5: Constraint doesn't match");
+    }
+
+    #[tokio::test]
+    async fn missing_imports() {
+        let mut store = Store::default();
+        let module = Module::new(
+            &store,
+            r#"(module
+                (import "runtime" "exceptionHandler" (func (param i32)))
+                (import "runtime" "notProvided" (func (param i32 i32)))
+                (import "other" "alsoNotProvided" (func)))"#,
+        )
+        .unwrap();
+        let err = WitnessCalculator::make_wasm_runtime(&mut store, module).unwrap_err();
+        let err = err.to_string();
+        assert!(err.contains("runtime::notProvided"));
+        assert!(err.contains("other::alsoNotProvided"));
+        assert!(!err.contains("exceptionHandler"));
+    }
+
     use serde_json::Value;
     use std::str::FromStr;
 
     fn value_to_bigint(v: Value) -> BigInt {
         match v {
-            Value::String(inner) => BigInt::from_str(&inner).unwrap(),
+            Value::String(inner) => match inner.strip_prefix("0x") {
+                Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16).unwrap(),
+                None => BigInt::from_str(&inner).unwrap(),
+            },
             Value::Number(inner) => BigInt::from(inner.as_u64().expect("not a u32")),
             _ => panic!("unsupported type"),
         }
@@ -489,11 +1598,19 @@ mod tests {
         );
         assert_eq!({ wtns.n64 }, case.n64);
 
-        let inputs_str = std::fs::read_to_string(case.inputs_path).unwrap();
+        let inputs = read_inputs(case.inputs_path);
+        let res = wtns.calculate_witness(&mut store, inputs, false).unwrap();
+        for (r, w) in res.iter().zip(case.witness) {
+            assert_eq!(r, &BigInt::from_str(w).unwrap());
+        }
+    }
+
+    fn read_inputs(path: &str) -> HashMap<String, Vec<BigInt>> {
+        let inputs_str = std::fs::read_to_string(path).unwrap();
         let inputs: std::collections::HashMap<String, serde_json::Value> =
             serde_json::from_str(&inputs_str).unwrap();
 
-        let inputs = inputs
+        inputs
             .iter()
             .map(|(key, value)| {
                 let res = match value {
@@ -509,11 +1626,6 @@ mod tests {
 
                 (key.clone(), res)
             })
-            .collect::<HashMap<_, _>>();
-
-        let res = wtns.calculate_witness(&mut store, inputs, false).unwrap();
-        for (r, w) in res.iter().zip(case.witness) {
-            assert_eq!(r, &BigInt::from_str(w).unwrap());
-        }
+            .collect::<HashMap<_, _>>()
     }
 }
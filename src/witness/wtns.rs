@@ -0,0 +1,204 @@
+//! Reader and writer for the binary `.wtns` witness files of snarkjs
+//!
+//! The file holds two sections:
+//!  Header(1)
+//!       n8
+//!       prime
+//!       NWitness
+//!  Witness(2)
+//!       the witness values, as n8-byte little-endian integers
+use ark_bn254::Fr;
+use ark_ff::{BigInteger256, PrimeField};
+use ark_serialize::CanonicalDeserialize;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use color_eyre::{eyre::eyre, Result};
+use num_bigint::{BigInt, BigUint, Sign};
+use std::{
+    collections::HashMap,
+    io::{Read, Seek, SeekFrom, Write},
+};
+
+/// Reads a witness in the `.wtns` format of snarkjs, e.g. to prove it with the proving key
+/// of [`read_zkey`](crate::read_zkey) without running the circuit's wasm. Fails if the
+/// witness is not for the scalar field of BN254.
+pub fn read_wtns<R: Read + Seek>(reader: &mut R) -> Result<Vec<Fr>> {
+    let (n8, prime, num_witness) = read_header(reader)?;
+    let modulus: BigUint = Fr::MODULUS.into();
+    if prime != modulus {
+        return Err(eyre!(
+            "the witness is for the field of prime {prime}, not the scalar field of BN254"
+        ));
+    }
+    if n8 != 32 {
+        return Err(eyre!(
+            "the witness values are {n8} bytes long instead of 32"
+        ));
+    }
+    (0..num_witness)
+        .map(|i| {
+            let value = BigInteger256::deserialize_uncompressed(&mut *reader)?;
+            Fr::from_bigint(value).ok_or_else(|| eyre!("witness value {i} is not a field element"))
+        })
+        .collect()
+}
+
+/// Reads a witness in the `.wtns` format of snarkjs for any field, returning its prime
+/// along with the values, as output by the native witness generators
+#[cfg(feature = "native")]
+pub(crate) fn read_wtns_bigint<R: Read + Seek>(reader: &mut R) -> Result<(BigInt, Vec<BigInt>)> {
+    let (n8, prime, num_witness) = read_header(reader)?;
+    let mut bytes = vec![0u8; n8];
+    let witness = (0..num_witness)
+        .map(|_| {
+            reader.read_exact(&mut bytes)?;
+            Ok(BigInt::from_bytes_le(Sign::Plus, &bytes))
+        })
+        .collect::<Result<_>>()?;
+    Ok((prime.into(), witness))
+}
+
+// Reads the section table and the header, returning the size of the values, the prime and
+// the number of values, and leaves `reader` at the start of the values
+fn read_header<R: Read + Seek>(reader: &mut R) -> Result<(usize, BigUint, usize)> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"wtns" {
+        return Err(eyre!("not a wtns file"));
+    }
+    let _version = reader.read_u32::<LittleEndian>()?;
+
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+    let mut sections = HashMap::new();
+    for _ in 0..num_sections {
+        let section_id = reader.read_u32::<LittleEndian>()?;
+        let section_length = reader.read_u64::<LittleEndian>()?;
+        sections.insert(section_id, (reader.stream_position()?, section_length));
+        reader.seek(SeekFrom::Current(section_length as i64))?;
+    }
+
+    seek_section(reader, &sections, 1)?;
+    let n8 = reader.read_u32::<LittleEndian>()? as usize;
+    let mut prime = vec![0u8; n8];
+    reader.read_exact(&mut prime)?;
+    let prime = BigUint::from_bytes_le(&prime);
+    let num_witness = reader.read_u32::<LittleEndian>()? as usize;
+
+    let length = seek_section(reader, &sections, 2)?;
+    if length != (n8 * num_witness) as u64 {
+        return Err(eyre!(
+            "the witness section holds {length} bytes, but {num_witness} values were declared"
+        ));
+    }
+    Ok((n8, prime, num_witness))
+}
+
+/// Writes a witness for the field of prime `prime` in the `.wtns` format of snarkjs, e.g.
+/// for proving it with `snarkjs groth16 prove`. Negative values are reduced modulo `prime`.
+pub fn write_wtns<W: Write>(writer: &mut W, prime: &BigInt, witness: &[BigInt]) -> Result<()> {
+    if prime.sign() != Sign::Plus {
+        return Err(eyre!("the prime {prime} is not positive"));
+    }
+    // the field size is rounded up to whole 64-bit limbs, like snarkjs does
+    let n8 = prime.bits().div_ceil(64) as usize * 8;
+
+    writer.write_all(b"wtns")?;
+    writer.write_u32::<LittleEndian>(2)?;
+    writer.write_u32::<LittleEndian>(2)?;
+
+    writer.write_u32::<LittleEndian>(1)?;
+    writer.write_u64::<LittleEndian>(n8 as u64 + 8)?;
+    writer.write_u32::<LittleEndian>(n8 as u32)?;
+    write_element(writer, prime, n8)?;
+    writer.write_u32::<LittleEndian>(witness.len() as u32)?;
+
+    writer.write_u32::<LittleEndian>(2)?;
+    writer.write_u64::<LittleEndian>((n8 * witness.len()) as u64)?;
+    for value in witness {
+        let value = ((value % prime) + prime) % prime;
+        write_element(writer, &value, n8)?;
+    }
+    Ok(())
+}
+
+// seeks to the start of a section, returning its length
+fn seek_section<R: Read + Seek>(
+    reader: &mut R,
+    sections: &HashMap<u32, (u64, u64)>,
+    id: u32,
+) -> Result<u64> {
+    let (position, length) = *sections
+        .get(&id)
+        .ok_or_else(|| eyre!("the wtns file has no section {id}"))?;
+    reader.seek(SeekFrom::Start(position))?;
+    Ok(length)
+}
+
+fn write_element<W: Write>(writer: &mut W, value: &BigInt, n8: usize) -> Result<()> {
+    let (_, mut bytes) = value.to_bytes_le();
+    bytes.resize(n8, 0);
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn rewrite_snarkjs_wtns() {
+        // a witness written by `snarkjs wtns calculate`
+        let data = std::fs::read("./test-vectors/witness.wtns").unwrap();
+        let prime = BigInt::from_bytes_le(Sign::Plus, &data[28..60]);
+        assert_eq!(
+            prime,
+            BigInt::from_str(
+                "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+            )
+            .unwrap()
+        );
+        let witness = data[76..]
+            .chunks(32)
+            .map(|chunk| BigInt::from_bytes_le(Sign::Plus, chunk))
+            .collect::<Vec<_>>();
+
+        let mut written = Vec::new();
+        write_wtns(&mut written, &prime, &witness).unwrap();
+        assert_eq!(written, data);
+
+        // negative values are written as their field element
+        let mut negative = Vec::new();
+        write_wtns(&mut negative, &prime, &[BigInt::from(-1)]).unwrap();
+        let value = BigInt::from_bytes_le(Sign::Plus, &negative[76..]);
+        assert_eq!(value, &prime - 1);
+    }
+
+    #[test]
+    fn read_snarkjs_wtns() {
+        let mut file = std::fs::File::open("./test-vectors/witness.wtns").unwrap();
+        let witness = read_wtns(&mut file).unwrap();
+        assert_eq!(witness.len(), 20001);
+        assert_eq!(witness[0], Fr::from(1));
+        assert_eq!(witness[2..4], [Fr::from(3), Fr::from(9)]);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let prime = BigInt::from(BigUint::from(Fr::MODULUS));
+        let witness = [1, 33, 3, 11].map(BigInt::from);
+        let mut data = Vec::new();
+        write_wtns(&mut data, &prime, &witness).unwrap();
+        let read = read_wtns(&mut std::io::Cursor::new(&data)).unwrap();
+        assert_eq!(read, [1, 33, 3, 11].map(Fr::from));
+
+        // witnesses for other fields are rejected, but can be read as integers
+        let mut data = Vec::new();
+        write_wtns(&mut data, &BigInt::from(7), &[BigInt::from(1)]).unwrap();
+        assert!(read_wtns(&mut std::io::Cursor::new(&data)).is_err());
+        #[cfg(feature = "native")]
+        {
+            let (prime, witness) = read_wtns_bigint(&mut std::io::Cursor::new(&data)).unwrap();
+            assert_eq!((prime, witness), (BigInt::from(7), vec![BigInt::from(1)]));
+        }
+    }
+}
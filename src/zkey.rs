@@ -25,31 +25,62 @@
 //!  PointsC(8)
 //!  PointsH(9)
 //!  Contributions(10)
-use ark_ff::{BigInteger256, PrimeField};
+use ark_ec::{
+    short_weierstrass::{Affine, SWCurveConfig},
+    AffineRepr,
+};
+use ark_ff::{BigInteger256, Field, PrimeField};
 use ark_relations::r1cs::ConstraintMatrices;
-use ark_serialize::{CanonicalDeserialize, SerializationError};
-use ark_std::log2;
-use byteorder::{LittleEndian, ReadBytesExt};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, SerializationError, SerializationError::IoError,
+};
+use ark_std::{cfg_iter, log2};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use std::{
     collections::HashMap,
-    io::{Read, Seek, SeekFrom},
+    io::{Error, ErrorKind, Read, Seek, SeekFrom, Write},
 };
 
 use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
-use ark_groth16::{ProvingKey, VerifyingKey};
-use num_traits::Zero;
+use ark_groth16::{prepare_verifying_key, PreparedVerifyingKey, ProvingKey, VerifyingKey};
+use num_traits::{One, Zero};
+
+use crate::circom::R1CS;
+
+// the setup of new zkeys runs the witness generator of the circuit
+#[cfg(feature = "wasmer")]
+use {
+    crate::{
+        circom::{CircomReduction, ConstraintVec},
+        CircomBuilder,
+    },
+    ark_groth16::Groth16,
+    ark_std::rand::{CryptoRng, RngCore, SeedableRng},
+    rand_chacha::ChaCha20Rng,
+};
 
 type IoResult<T> = Result<T, SerializationError>;
 
 #[derive(Clone, Debug)]
 struct Section {
     position: u64,
-    #[allow(dead_code)]
     size: usize,
 }
 
+// the sizes of the points of a zkey, which holds both of their coordinates
+const G1_SIZE: usize = 64;
+const G2_SIZE: usize = 128;
+
+// the magic of the zkeys of snarkjs
+const MAGIC: &[u8; 4] = b"zkey";
+
 /// Reads a SnarkJS ZKey file into an Arkworks ProvingKey.
+///
+/// The points are not checked to be on the curve and in the prime order subgroup, use
+/// [`read_zkey_checked`] for keys from untrusted sources.
 pub fn read_zkey<R: Read + Seek>(
     reader: &mut R,
 ) -> IoResult<(ProvingKey<Bn254>, ConstraintMatrices<Fr>)> {
@@ -59,20 +90,377 @@ pub fn read_zkey<R: Read + Seek>(
     Ok((proving_key, matrices))
 }
 
+/// Reads a SnarkJS ZKey file like [`read_zkey`], and checks that every point of the
+/// proving key is on the curve and in the prime order subgroup. Fails with the first
+/// invalid point, see [`validate_proving_key_parallel`].
+pub fn read_zkey_checked<R: Read + Seek>(
+    reader: &mut R,
+) -> IoResult<(ProvingKey<Bn254>, ConstraintMatrices<Fr>)> {
+    let (proving_key, matrices) = read_zkey(reader)?;
+    validate_proving_key_parallel(&proving_key)
+        .map_err(|err| IoError(Error::new(ErrorKind::InvalidData, err.to_string())))?;
+    Ok((proving_key, matrices))
+}
+
+/// Downloads a SnarkJS ZKey file from `url` and reads it like [`read_zkey`]
+#[cfg(feature = "fetch")]
+pub async fn read_zkey_from_url(
+    url: &str,
+) -> color_eyre::Result<(ProvingKey<Bn254>, ConstraintMatrices<Fr>)> {
+    let bytes = crate::fetch::fetch(url).await?;
+    Ok(read_zkey(&mut std::io::Cursor::new(bytes))?)
+}
+
+/// Reads a SnarkJS ZKey file like [`read_zkey`], but maps the file into memory and parses
+/// the point sections straight out of the mapped pages, without copying them into
+/// intermediate buffers first.
+///
+/// This does not lower the peak memory usage: the mapped pages count towards the resident
+/// set of the process on top of the parsed key, so the peak RSS is higher than with
+/// [`read_zkey`] over a `BufReader`.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated, by this or any other process, until this
+/// function returns. Changing a mapped file is undefined behavior rather than an I/O error.
+#[cfg(feature = "mmap")]
+pub unsafe fn read_zkey_mmap(
+    path: impl AsRef<std::path::Path>,
+) -> IoResult<(ProvingKey<Bn254>, ConstraintMatrices<Fr>)> {
+    let file = std::fs::File::open(path)?;
+    // SAFETY: the caller guarantees that the file is not modified while it is mapped, and
+    // the map is dropped before returning
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    let bytes = &map[..];
+    let mut cursor = std::io::Cursor::new(bytes);
+    let mut binfile = BinFile::new(&mut cursor)?;
+    binfile.bytes = Some(bytes);
+    let proving_key = binfile.proving_key()?;
+    let matrices = binfile.matrices()?;
+    Ok((proving_key, matrices))
+}
+
+/// Writes an Arkworks ProvingKey and the A and B matrices of its circuit as a SnarkJS
+/// ZKey file. The matrices must not include the public input constraints, as returned by
+/// [`read_zkey`], and the proving key must have been generated with
+/// [`CircomReduction`](crate::CircomReduction) for its H query to match what SnarkJS expects.
+///
+/// No ceremony information is written: the Contributions section holds an empty circuit
+/// hash and no contributions.
+pub fn write_zkey<W: Write>(
+    pk: &ProvingKey<Bn254>,
+    matrices: &ConstraintMatrices<Fr>,
+    writer: &mut W,
+) -> IoResult<()> {
+    let n_vars = pk.a_query.len();
+    let n_public = pk.vk.gamma_abc_g1.len() - 1;
+    let domain_size = pk.h_query.len();
+
+    writer.write_all(MAGIC)?;
+    writer.write_u32::<LittleEndian>(1)?;
+    writer.write_u32::<LittleEndian>(10)?;
+
+    // Groth16
+    write_section(writer, 1, &1u32.to_le_bytes())?;
+
+    let mut header = Vec::new();
+    header.write_u32::<LittleEndian>(32)?;
+    Fq::MODULUS.serialize_uncompressed(&mut header)?;
+    header.write_u32::<LittleEndian>(32)?;
+    Fr::MODULUS.serialize_uncompressed(&mut header)?;
+    header.write_u32::<LittleEndian>(n_vars as u32)?;
+    header.write_u32::<LittleEndian>(n_public as u32)?;
+    header.write_u32::<LittleEndian>(domain_size as u32)?;
+    serialize_g1(&mut header, &pk.vk.alpha_g1)?;
+    serialize_g1(&mut header, &pk.beta_g1)?;
+    serialize_g2(&mut header, &pk.vk.beta_g2)?;
+    serialize_g2(&mut header, &pk.vk.gamma_g2)?;
+    serialize_g1(&mut header, &pk.delta_g1)?;
+    serialize_g2(&mut header, &pk.vk.delta_g2)?;
+    write_section(writer, 2, &header)?;
+
+    // snarkjs adds the public input constraints to A, after the circuit's constraints
+    let mut coeffs = Vec::new();
+    let mut num_coeffs = 0u32;
+    for (matrix, rows) in [&matrices.a, &matrices.b].into_iter().enumerate() {
+        for (constraint, row) in rows.iter().enumerate() {
+            for (value, signal) in row {
+                write_coeff(&mut coeffs, matrix, constraint, *signal, value)?;
+                num_coeffs += 1;
+            }
+        }
+    }
+    for signal in 0..=n_public {
+        let constraint = matrices.num_constraints + signal;
+        write_coeff(&mut coeffs, 0, constraint, signal, &Fr::one())?;
+        num_coeffs += 1;
+    }
+    let mut section = num_coeffs.to_le_bytes().to_vec();
+    section.extend(coeffs);
+
+    // sections are written in the same order as snarkjs does
+    write_section(writer, 4, &section)?;
+    write_section(writer, 3, &serialize_g1_vec(&pk.vk.gamma_abc_g1)?)?;
+    write_section(writer, 9, &serialize_g1_vec(&pk.h_query)?)?;
+    write_section(writer, 8, &serialize_g1_vec(&pk.l_query)?)?;
+    write_section(writer, 5, &serialize_g1_vec(&pk.a_query)?)?;
+    write_section(writer, 6, &serialize_g1_vec(&pk.b_g1_query)?)?;
+    let mut section = Vec::new();
+    for point in &pk.b_g2_query {
+        serialize_g2(&mut section, point)?;
+    }
+    write_section(writer, 7, &section)?;
+
+    let mut section = vec![0u8; 64];
+    section.write_u32::<LittleEndian>(0)?;
+    write_section(writer, 10, &section)?;
+
+    Ok(())
+}
+
+/// Runs a Groth16 setup for the circuit of the builder and writes the resulting proving
+/// key as a SnarkJS ZKey file.
+///
+/// The toxic waste of this setup is only as secret as the provided rng, and no ceremony
+/// takes place, so the resulting ZKey must only be used for development and testing.
+#[cfg(feature = "wasmer")]
+pub fn setup_and_write_zkey<R: RngCore + CryptoRng, W: Write>(
+    builder: &CircomBuilder<Fr>,
+    rng: &mut R,
+    writer: &mut W,
+) -> color_eyre::Result<()> {
+    let circom = builder.setup();
+    let matrices = r1cs_matrices(&circom.r1cs);
+    let pk =
+        Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(circom, rng)?;
+    write_zkey(&pk, &matrices, writer)?;
+    Ok(())
+}
+
+/// Runs a Groth16 setup for the circuit of the builder with an rng seeded from `seed`, so
+/// that the same seed always results in the same proving key, e.g. to check in a verifier
+/// contract for tests. The rng is ChaCha20 keyed with the little-endian bytes of the
+/// seed, whose output does not change between versions, unlike `StdRng`.
+///
+/// Anyone knowing the seed can forge proofs, so the key must only be used for testing.
+#[cfg(feature = "wasmer")]
+pub fn generate_parameters_from_seed(
+    builder: &CircomBuilder<Fr>,
+    seed: u64,
+) -> color_eyre::Result<ProvingKey<Bn254>> {
+    let mut key = [0u8; 32];
+    key[..8].copy_from_slice(&seed.to_le_bytes());
+    let mut rng = ChaCha20Rng::from_seed(key);
+    let circom = builder.setup();
+    let pk = Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(
+        circom, &mut rng,
+    )?;
+    Ok(pk)
+}
+
+/// Checks that a proving key, e.g. read from a zkey, is for the circuit of `r1cs`, by
+/// comparing their numbers of variables and public inputs. Proving with mismatched files
+/// would otherwise produce proofs which do not verify.
+pub fn check_zkey_matches_r1cs(pk: &ProvingKey<Bn254>, r1cs: &R1CS<Fr>) -> color_eyre::Result<()> {
+    let n_vars = pk.a_query.len();
+    if n_vars != r1cs.num_variables {
+        return Err(color_eyre::eyre::eyre!(
+            "zkey n_vars ({n_vars}) != r1cs n_wires ({})",
+            r1cs.num_variables
+        ));
+    }
+    let n_public = pk.vk.gamma_abc_g1.len();
+    if n_public != r1cs.num_inputs {
+        return Err(color_eyre::eyre::eyre!(
+            "zkey n_public ({}) != r1cs n_public ({})",
+            n_public - 1,
+            r1cs.num_inputs - 1
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "wasmer")]
+fn r1cs_matrices(r1cs: &R1CS<Fr>) -> ConstraintMatrices<Fr> {
+    let matrix = |lcs: Vec<&ConstraintVec<Fr>>| -> Vec<Vec<(Fr, usize)>> {
+        lcs.into_iter()
+            .map(|lc| lc.iter().map(|(index, value)| (*value, *index)).collect())
+            .collect()
+    };
+    let a = matrix(r1cs.constraints.iter().map(|(a, _, _)| a).collect());
+    let b = matrix(r1cs.constraints.iter().map(|(_, b, _)| b).collect());
+    let c = matrix(r1cs.constraints.iter().map(|(_, _, c)| c).collect());
+    ConstraintMatrices {
+        num_instance_variables: r1cs.num_inputs,
+        num_witness_variables: r1cs.num_aux,
+        num_constraints: r1cs.constraints.len(),
+
+        a_num_non_zero: a.iter().map(|lc| lc.len()).sum(),
+        b_num_non_zero: b.iter().map(|lc| lc.len()).sum(),
+        c_num_non_zero: c.iter().map(|lc| lc.len()).sum(),
+
+        a,
+        b,
+        c,
+    }
+}
+
+fn write_section<W: Write>(writer: &mut W, id: u32, data: &[u8]) -> IoResult<()> {
+    writer.write_u32::<LittleEndian>(id)?;
+    writer.write_u64::<LittleEndian>(data.len() as u64)?;
+    writer.write_all(data)?;
+    Ok(())
+}
+
+fn write_coeff<W: Write>(
+    writer: &mut W,
+    matrix: usize,
+    constraint: usize,
+    signal: usize,
+    value: &Fr,
+) -> IoResult<()> {
+    writer.write_u32::<LittleEndian>(matrix as u32)?;
+    writer.write_u32::<LittleEndian>(constraint as u32)?;
+    writer.write_u32::<LittleEndian>(signal as u32)?;
+    serialize_field_fr(writer, value)
+}
+
+/// Reads the number of constraints of a SnarkJS ZKey file, without reading its keys or
+/// the coefficients of its matrices.
+pub fn read_zkey_num_constraints<R: Read + Seek>(reader: &mut R) -> IoResult<usize> {
+    let mut binfile = BinFile::new(reader)?;
+    binfile.num_constraints()
+}
+
+/// Reads the number of public inputs of a SnarkJS ZKey file, i.e. `n_public` of its
+/// header, without reading its keys or the coefficients of its matrices.
+pub fn read_zkey_num_public<R: Read + Seek>(reader: &mut R) -> IoResult<usize> {
+    let mut binfile = BinFile::new(reader)?;
+    Ok(binfile.groth_header()?.n_public)
+}
+
+/// Reads the IC points of a SnarkJS ZKey file, i.e. the `gamma_abc_g1` points of its
+/// verifying key, without reading the rest of the proving key.
+pub fn read_ic_points<R: Read + Seek>(reader: &mut R) -> IoResult<Vec<G1Affine>> {
+    let mut binfile = BinFile::new(reader)?;
+    let header = binfile.groth_header()?;
+    binfile.ic(header.n_public)
+}
+
+/// Reads the verifying key of a SnarkJS ZKey file from its header and IC sections only,
+/// skipping the points of the proving key and the matrices, which make up almost all of
+/// the file.
+pub fn read_zkey_vk<R: Read + Seek>(reader: &mut R) -> IoResult<VerifyingKey<Bn254>> {
+    let mut binfile = BinFile::new(reader)?;
+    binfile.verifying_key()
+}
+
+/// Reads the verifying key of a SnarkJS ZKey file like [`read_zkey_vk`] and prepares it
+/// for verification.
+pub fn read_prepared_vk<R: Read + Seek>(reader: &mut R) -> IoResult<PreparedVerifyingKey<Bn254>> {
+    let vk = read_zkey_vk(reader)?;
+    Ok(prepare_verifying_key(&vk))
+}
+
+/// A point of a proving key which is not on the curve or not in the prime order subgroup
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+#[error("invalid point at index {index} of `{section}`")]
+pub struct InvalidPoint {
+    /// Name of the ProvingKey field holding the point, e.g. `a_query`
+    pub section: &'static str,
+    pub index: usize,
+}
+
+/// Checks that every point of a proving key is on the curve and in the prime order
+/// subgroup, in parallel with the `parallel` feature. Useful to validate a key once after loading it without checks,
+/// and returns the first invalid point otherwise.
+pub fn validate_proving_key_parallel(pk: &ProvingKey<Bn254>) -> Result<(), InvalidPoint> {
+    fn check<P: SWCurveConfig>(
+        section: &'static str,
+        points: &[Affine<P>],
+    ) -> Result<(), InvalidPoint> {
+        let invalid = cfg_iter!(points)
+            .enumerate()
+            .filter(|(_, p)| !(p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve()))
+            .map(|(index, _)| index)
+            .min();
+        match invalid {
+            Some(index) => Err(InvalidPoint { section, index }),
+            None => Ok(()),
+        }
+    }
+
+    let vk = &pk.vk;
+    check("alpha_g1", &[vk.alpha_g1])?;
+    check("beta_g1", &[pk.beta_g1])?;
+    check("delta_g1", &[pk.delta_g1])?;
+    check("beta_g2", &[vk.beta_g2])?;
+    check("gamma_g2", &[vk.gamma_g2])?;
+    check("delta_g2", &[vk.delta_g2])?;
+    check("gamma_abc_g1", &vk.gamma_abc_g1)?;
+    check("a_query", &pk.a_query)?;
+    check("b_g1_query", &pk.b_g1_query)?;
+    check("b_g2_query", &pk.b_g2_query)?;
+    check("h_query", &pk.h_query)?;
+    check("l_query", &pk.l_query)
+}
+
+/// Information about the trusted setup ceremony, as recorded in the Contributions section
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CeremonyInfo {
+    /// Hash of the circuit the ceremony was run for
+    pub cs_hash: [u8; 64],
+    pub contributions: Vec<CeremonyContribution>,
+}
+
+/// A single contribution to the phase 2 ceremony
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CeremonyContribution {
+    /// Value of delta after the contribution was applied
+    pub delta_after: G1Affine,
+    /// Hash of the ceremony transcript that the contribution was made on top of
+    pub transcript: [u8; 64],
+    /// 0 for a regular contribution, 1 for a random beacon
+    pub contribution_type: u32,
+    pub name: Option<String>,
+    /// Exponent of the number of hash iterations used by a beacon
+    pub num_iterations_exp: Option<u8>,
+    pub beacon_hash: Option<Vec<u8>>,
+}
+
+/// Reads the trusted setup ceremony information of a SnarkJS ZKey file, so that its
+/// contributions can be cross-checked against a published ceremony transcript.
+pub fn read_zkey_ceremony_info<R: Read + Seek>(reader: &mut R) -> IoResult<CeremonyInfo> {
+    let mut binfile = BinFile::new(reader)?;
+    binfile.ceremony_info()
+}
+
+/// A reader over the sections of a zkey file. Besides the eager [`read_zkey`], it gives
+/// access to the point sections one point at a time through e.g. [`BinFile::a_query_iter`],
+/// so that large keys can be streamed without holding every section in memory.
 #[derive(Debug)]
-struct BinFile<'a, R> {
+pub struct BinFile<'a, R> {
     #[allow(dead_code)]
     ftype: String,
     #[allow(dead_code)]
     version: u32,
     sections: HashMap<u32, Vec<Section>>,
     reader: &'a mut R,
+    // the whole file, when it is in memory, so that point sections are parsed in place
+    bytes: Option<&'a [u8]>,
 }
 
 impl<'a, R: Read + Seek> BinFile<'a, R> {
-    fn new(reader: &'a mut R) -> IoResult<Self> {
+    /// Reads the section table of the zkey from `reader`
+    pub fn new(reader: &'a mut R) -> IoResult<Self> {
         let mut magic = [0u8; 4];
         reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(IoError(Error::new(
+                ErrorKind::InvalidData,
+                "Invalid magic, expected a zkey file",
+            )));
+        }
 
         let version = reader.read_u32::<LittleEndian>()?;
 
@@ -97,12 +485,25 @@ impl<'a, R: Read + Seek> BinFile<'a, R> {
             version,
             sections,
             reader,
+            bytes: None,
         })
     }
 
-    fn proving_key(&mut self) -> IoResult<ProvingKey<Bn254>> {
+    fn verifying_key(&mut self) -> IoResult<VerifyingKey<Bn254>> {
         let header = self.groth_header()?;
         let ic = self.ic(header.n_public)?;
+        Ok(VerifyingKey::<Bn254> {
+            alpha_g1: header.verifying_key.alpha_g1,
+            beta_g2: header.verifying_key.beta_g2,
+            gamma_g2: header.verifying_key.gamma_g2,
+            delta_g2: header.verifying_key.delta_g2,
+            gamma_abc_g1: ic,
+        })
+    }
+
+    fn proving_key(&mut self) -> IoResult<ProvingKey<Bn254>> {
+        let header = self.groth_header()?;
+        let vk = self.verifying_key()?;
 
         let a_query = self.a_query(header.n_vars)?;
         let b_g1_query = self.b_g1_query(header.n_vars)?;
@@ -110,14 +511,6 @@ impl<'a, R: Read + Seek> BinFile<'a, R> {
         let l_query = self.l_query(header.n_vars - header.n_public - 1)?;
         let h_query = self.h_query(header.domain_size as usize)?;
 
-        let vk = VerifyingKey::<Bn254> {
-            alpha_g1: header.verifying_key.alpha_g1,
-            beta_g2: header.verifying_key.beta_g2,
-            gamma_g2: header.verifying_key.gamma_g2,
-            delta_g2: header.verifying_key.delta_g2,
-            gamma_abc_g1: ic,
-        };
-
         let pk = ProvingKey::<Bn254> {
             vk,
             beta_g1: header.verifying_key.beta_g1,
@@ -137,11 +530,30 @@ impl<'a, R: Read + Seek> BinFile<'a, R> {
     }
 
     fn groth_header(&mut self) -> IoResult<HeaderGroth> {
+        self.check_groth16()?;
         let section = self.get_section(2);
         let header = HeaderGroth::new(&mut self.reader, &section)?;
         Ok(header)
     }
 
+    // the prover type of Header(1) tells which header follows, so other zkeys are rejected
+    // before their header is parsed as a Groth16 one
+    fn check_groth16(&mut self) -> IoResult<()> {
+        let section = self.get_section(1);
+        self.reader.seek(SeekFrom::Start(section.position))?;
+        let prover_type = self.reader.read_u32::<LittleEndian>()?;
+        let name = match prover_type {
+            1 => return Ok(()),
+            2 => "PLONK",
+            10 => "FFLONK",
+            _ => "an unknown prover",
+        };
+        Err(IoError(Error::new(
+            ErrorKind::InvalidData,
+            format!("expected Groth16 zkey, found {name} (type {prover_type})"),
+        )))
+    }
+
     fn ic(&mut self, n_public: usize) -> IoResult<Vec<G1Affine>> {
         // the range is non-inclusive so we do +1 to get all inputs
         self.g1_section(n_public + 1, 3)
@@ -195,6 +607,59 @@ impl<'a, R: Read + Seek> BinFile<'a, R> {
         Ok(matrices)
     }
 
+    fn num_constraints(&mut self) -> IoResult<usize> {
+        let header = self.groth_header()?;
+
+        // snarkjs writes the coefficients of the public input constraints last, so the
+        // final coefficient belongs to the constraint with the highest index
+        let section = self.get_section(4);
+        let coeff_size = 3 * 4 + 32;
+        if section.size < 4 + coeff_size {
+            return Err(IoError(Error::new(
+                ErrorKind::InvalidData,
+                "Coefficients section is too small",
+            )));
+        }
+        let last = section.position + (section.size - coeff_size) as u64;
+        self.reader.seek(SeekFrom::Start(last))?;
+        let _matrix = self.reader.read_u32::<LittleEndian>()?;
+        let constraint = self.reader.read_u32::<LittleEndian>()? as usize;
+
+        constraint.checked_sub(header.n_public).ok_or_else(|| {
+            IoError(Error::new(
+                ErrorKind::InvalidData,
+                "Coefficients section does not end with the public input constraints",
+            ))
+        })
+    }
+
+    fn ceremony_info(&mut self) -> IoResult<CeremonyInfo> {
+        let section = self
+            .sections
+            .get(&10)
+            .map(|sections| sections[0].clone())
+            .ok_or_else(|| {
+                IoError(Error::new(
+                    ErrorKind::InvalidData,
+                    "No contributions section found",
+                ))
+            })?;
+        self.reader.seek(SeekFrom::Start(section.position))?;
+
+        let mut cs_hash = [0u8; 64];
+        self.reader.read_exact(&mut cs_hash)?;
+
+        let num_contributions = self.reader.read_u32::<LittleEndian>()?;
+        let contributions = (0..num_contributions)
+            .map(|_| CeremonyContribution::read(&mut self.reader))
+            .collect::<IoResult<_>>()?;
+
+        Ok(CeremonyInfo {
+            cs_hash,
+            contributions,
+        })
+    }
+
     fn a_query(&mut self, n_vars: usize) -> IoResult<Vec<G1Affine>> {
         self.g1_section(n_vars, 5)
     }
@@ -215,17 +680,81 @@ impl<'a, R: Read + Seek> BinFile<'a, R> {
         self.g1_section(n_vars, 9)
     }
 
+    /// Returns an iterator over the `a_query` points of the proving key
+    pub fn a_query_iter(&mut self) -> IoResult<impl Iterator<Item = IoResult<G1Affine>> + '_> {
+        let header = self.groth_header()?;
+        self.g1_section_iter(header.n_vars, 5)
+    }
+
+    /// Returns an iterator over the `b_g1_query` points of the proving key
+    pub fn b_g1_query_iter(&mut self) -> IoResult<impl Iterator<Item = IoResult<G1Affine>> + '_> {
+        let header = self.groth_header()?;
+        self.g1_section_iter(header.n_vars, 6)
+    }
+
+    /// Returns an iterator over the `b_g2_query` points of the proving key
+    pub fn b_g2_query_iter(&mut self) -> IoResult<impl Iterator<Item = IoResult<G2Affine>> + '_> {
+        let header = self.groth_header()?;
+        self.g2_section_iter(header.n_vars, 7)
+    }
+
+    /// Returns an iterator over the `l_query` points of the proving key
+    pub fn l_query_iter(&mut self) -> IoResult<impl Iterator<Item = IoResult<G1Affine>> + '_> {
+        let header = self.groth_header()?;
+        self.g1_section_iter(header.n_vars - header.n_public - 1, 8)
+    }
+
+    /// Returns an iterator over the `h_query` points of the proving key
+    pub fn h_query_iter(&mut self) -> IoResult<impl Iterator<Item = IoResult<G1Affine>> + '_> {
+        let header = self.groth_header()?;
+        self.g1_section_iter(header.domain_size as usize, 9)
+    }
+
     fn g1_section(&mut self, num: usize, section_id: usize) -> IoResult<Vec<G1Affine>> {
         let section = self.get_section(section_id as u32);
+        if let Some(bytes) = self.section_bytes(&section) {
+            return deserialize_g1_slice(bytes, num);
+        }
         self.reader.seek(SeekFrom::Start(section.position))?;
         deserialize_g1_vec(self.reader, num as u32)
     }
 
     fn g2_section(&mut self, num: usize, section_id: usize) -> IoResult<Vec<G2Affine>> {
         let section = self.get_section(section_id as u32);
+        if let Some(bytes) = self.section_bytes(&section) {
+            return deserialize_g2_slice(bytes, num);
+        }
         self.reader.seek(SeekFrom::Start(section.position))?;
         deserialize_g2_vec(self.reader, num as u32)
     }
+
+    // the bytes of a section, if the file is in memory
+    fn section_bytes(&self, section: &Section) -> Option<&'a [u8]> {
+        let start = usize::try_from(section.position).ok()?;
+        self.bytes?.get(start..)?.get(..section.size)
+    }
+
+    fn g1_section_iter(
+        &mut self,
+        num: usize,
+        section_id: usize,
+    ) -> IoResult<impl Iterator<Item = IoResult<G1Affine>> + '_> {
+        let section = self.get_section(section_id as u32);
+        self.reader.seek(SeekFrom::Start(section.position))?;
+        let reader = &mut *self.reader;
+        Ok((0..num).map(move |_| deserialize_g1(&mut *reader)))
+    }
+
+    fn g2_section_iter(
+        &mut self,
+        num: usize,
+        section_id: usize,
+    ) -> IoResult<impl Iterator<Item = IoResult<G2Affine>> + '_> {
+        let section = self.get_section(section_id as u32);
+        self.reader.seek(SeekFrom::Start(section.position))?;
+        let reader = &mut *self.reader;
+        Ok((0..num).map(move |_| deserialize_g2(&mut *reader)))
+    }
 }
 
 #[derive(Default, Clone, Debug, CanonicalDeserialize)]
@@ -258,6 +787,54 @@ impl ZVerifyingKey {
     }
 }
 
+impl CeremonyContribution {
+    fn read<R: Read>(reader: &mut R) -> IoResult<Self> {
+        let delta_after = deserialize_g1(reader)?;
+        // proof of knowledge of the contribution, not needed to identify it
+        let _g1_s = deserialize_g1(reader)?;
+        let _g1_sx = deserialize_g1(reader)?;
+        let _g2_spx = deserialize_g2(reader)?;
+
+        let mut transcript = [0u8; 64];
+        reader.read_exact(&mut transcript)?;
+        let contribution_type = reader.read_u32::<LittleEndian>()?;
+
+        let mut contribution = Self {
+            delta_after,
+            transcript,
+            contribution_type,
+            name: None,
+            num_iterations_exp: None,
+            beacon_hash: None,
+        };
+
+        // the optional parameters are stored as (type, value) pairs sorted by type
+        let params_len = reader.read_u32::<LittleEndian>()?;
+        let mut params = reader.take(params_len as u64);
+        let invalid = |msg| IoError(Error::new(ErrorKind::InvalidData, msg));
+        while params.limit() > 0 {
+            match params.read_u8()? {
+                1 => {
+                    let mut name = vec![0u8; params.read_u8()? as usize];
+                    params.read_exact(&mut name)?;
+                    let name = String::from_utf8(name)
+                        .map_err(|_| invalid("Invalid contribution name"))?;
+                    contribution.name = Some(name);
+                }
+                2 => contribution.num_iterations_exp = Some(params.read_u8()?),
+                3 => {
+                    let mut hash = vec![0u8; params.read_u8()? as usize];
+                    params.read_exact(&mut hash)?;
+                    contribution.beacon_hash = Some(hash);
+                }
+                _ => return Err(invalid("Unknown contribution parameter")),
+            }
+        }
+
+        Ok(contribution)
+    }
+}
+
 #[derive(Clone, Debug)]
 struct HeaderGroth {
     #[allow(dead_code)]
@@ -289,11 +866,11 @@ impl HeaderGroth {
         // TODO: Impl From<u32> in Arkworks
         let n8q: u32 = u32::deserialize_uncompressed(&mut reader)?;
         // group order r of Bn254
-        let q = BigInteger256::deserialize_uncompressed(&mut reader)?;
+        let q = deserialize_bigint(reader)?;
 
         let n8r: u32 = u32::deserialize_uncompressed(&mut reader)?;
         // Prime field modulus
-        let r = BigInteger256::deserialize_uncompressed(&mut reader)?;
+        let r = deserialize_bigint(reader)?;
 
         let n_vars = u32::deserialize_uncompressed(&mut reader)? as usize;
         let n_public = u32::deserialize_uncompressed(&mut reader)? as usize;
@@ -319,14 +896,22 @@ impl HeaderGroth {
 
 // need to divide by R, since snarkjs outputs the zkey with coefficients
 // multiplieid by R^2
+// reads the little-endian limbs directly: ark-serialize 0.4 panics instead of returning an
+// error when the reader ends within an integer
+fn deserialize_bigint<R: Read>(reader: &mut R) -> IoResult<BigInteger256> {
+    let mut limbs = [0u64; 4];
+    reader.read_u64_into::<LittleEndian>(&mut limbs)?;
+    Ok(BigInteger256::new(limbs))
+}
+
 fn deserialize_field_fr<R: Read>(reader: &mut R) -> IoResult<Fr> {
-    let bigint = BigInteger256::deserialize_uncompressed(reader)?;
+    let bigint = deserialize_bigint(reader)?;
     Ok(Fr::new_unchecked(Fr::new_unchecked(bigint).into_bigint()))
 }
 
 // skips the multiplication by R because Circom points are already in Montgomery form
 fn deserialize_field<R: Read>(reader: &mut R) -> IoResult<Fq> {
-    let bigint = BigInteger256::deserialize_uncompressed(reader)?;
+    let bigint = deserialize_bigint(reader)?;
     // if you use Fq::new it multiplies by R
     Ok(Fq::new_unchecked(bigint))
 }
@@ -344,7 +929,8 @@ fn deserialize_g1<R: Read>(reader: &mut R) -> IoResult<G1Affine> {
     if infinity {
         Ok(G1Affine::identity())
     } else {
-        Ok(G1Affine::new(x, y))
+        // the points are validated by `read_zkey_checked`, as the checks are expensive
+        Ok(G1Affine::new_unchecked(x, y))
     }
 }
 
@@ -355,38 +941,180 @@ fn deserialize_g2<R: Read>(reader: &mut R) -> IoResult<G2Affine> {
     if infinity {
         Ok(G2Affine::identity())
     } else {
-        Ok(G2Affine::new(f1, f2))
+        Ok(G2Affine::new_unchecked(f1, f2))
     }
 }
 
+// multiplies by R, the inverse of `deserialize_field_fr`
+fn serialize_field_fr<W: Write>(writer: &mut W, value: &Fr) -> IoResult<()> {
+    // the Montgomery form of `value` is always a canonical element of the field
+    Fr::from_bigint(value.0)
+        .unwrap()
+        .0
+        .serialize_uncompressed(writer)
+}
+
+// writes the Montgomery form, as expected by Circom
+fn serialize_field<W: Write>(writer: &mut W, value: &Fq) -> IoResult<()> {
+    value.0.serialize_uncompressed(writer)
+}
+
+fn serialize_g1<W: Write>(writer: &mut W, point: &G1Affine) -> IoResult<()> {
+    // the point at infinity is encoded as zeros, which `xy` leaves out
+    let (x, y) = point.xy().unwrap_or((&Fq::ZERO, &Fq::ZERO));
+    serialize_field(writer, x)?;
+    serialize_field(writer, y)
+}
+
+fn serialize_g2<W: Write>(writer: &mut W, point: &G2Affine) -> IoResult<()> {
+    let (x, y) = point.xy().unwrap_or((&Fq2::ZERO, &Fq2::ZERO));
+    for f in [x, y] {
+        serialize_field(writer, &f.c0)?;
+        serialize_field(writer, &f.c1)?;
+    }
+    Ok(())
+}
+
+fn serialize_g1_vec(points: &[G1Affine]) -> IoResult<Vec<u8>> {
+    let mut buf = Vec::with_capacity(points.len() * G1_SIZE);
+    for point in points {
+        serialize_g1(&mut buf, point)?;
+    }
+    Ok(buf)
+}
+
+// with the `parallel` feature the section is read into one buffer and the points, each
+// of a fixed size, are parsed in parallel
 fn deserialize_g1_vec<R: Read>(reader: &mut R, n_vars: u32) -> IoResult<Vec<G1Affine>> {
-    (0..n_vars).map(|_| deserialize_g1(reader)).collect()
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "parallel")] {
+            let buf = read_points_bytes(reader, n_vars, G1_SIZE)?;
+            deserialize_g1_slice(&buf, n_vars as usize)
+        } else {
+            (0..n_vars).map(|_| deserialize_g1(reader)).collect()
+        }
+    }
 }
 
 fn deserialize_g2_vec<R: Read>(reader: &mut R, n_vars: u32) -> IoResult<Vec<G2Affine>> {
-    (0..n_vars).map(|_| deserialize_g2(reader)).collect()
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "parallel")] {
+            let buf = read_points_bytes(reader, n_vars, G2_SIZE)?;
+            deserialize_g2_slice(&buf, n_vars as usize)
+        } else {
+            (0..n_vars).map(|_| deserialize_g2(reader)).collect()
+        }
+    }
+}
+
+// reads the bytes of `num` points of `size` bytes. The buffer only grows with the bytes
+// actually read, so a point count forged in the header cannot allocate more memory than
+// the file holds, and a short read is reported when the points are parsed.
+#[cfg(feature = "parallel")]
+fn read_points_bytes<R: Read>(reader: &mut R, num: u32, size: usize) -> IoResult<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader
+        .take(num as u64 * size as u64)
+        .read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+// parses `num` points out of the bytes of a section, in parallel with the `parallel` feature
+fn deserialize_g1_slice(bytes: &[u8], num: usize) -> IoResult<Vec<G1Affine>> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "parallel")] {
+            deserialize_g1_points_parallel(bytes, num)
+        } else {
+            deserialize_g1_points_sequential(bytes, num)
+        }
+    }
+}
+
+fn deserialize_g2_slice(bytes: &[u8], num: usize) -> IoResult<Vec<G2Affine>> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "parallel")] {
+            deserialize_g2_points_parallel(bytes, num)
+        } else {
+            deserialize_g2_points_sequential(bytes, num)
+        }
+    }
+}
+
+// Both paths are public for `benches/zkey_load.rs`, which compares them side by side.
+
+#[doc(hidden)]
+pub fn deserialize_g1_points_sequential(bytes: &[u8], num: usize) -> IoResult<Vec<G1Affine>> {
+    let bytes = points_bytes(bytes, num, G1_SIZE)?;
+    bytes
+        .chunks(G1_SIZE)
+        .map(|mut chunk| deserialize_g1(&mut chunk))
+        .collect()
+}
+
+#[doc(hidden)]
+#[cfg(feature = "parallel")]
+pub fn deserialize_g1_points_parallel(bytes: &[u8], num: usize) -> IoResult<Vec<G1Affine>> {
+    let bytes = points_bytes(bytes, num, G1_SIZE)?;
+    bytes
+        .par_chunks(G1_SIZE)
+        .map(|mut chunk| deserialize_g1(&mut chunk))
+        .collect()
+}
+
+#[doc(hidden)]
+pub fn deserialize_g2_points_sequential(bytes: &[u8], num: usize) -> IoResult<Vec<G2Affine>> {
+    let bytes = points_bytes(bytes, num, G2_SIZE)?;
+    bytes
+        .chunks(G2_SIZE)
+        .map(|mut chunk| deserialize_g2(&mut chunk))
+        .collect()
+}
+
+#[doc(hidden)]
+#[cfg(feature = "parallel")]
+pub fn deserialize_g2_points_parallel(bytes: &[u8], num: usize) -> IoResult<Vec<G2Affine>> {
+    let bytes = points_bytes(bytes, num, G2_SIZE)?;
+    bytes
+        .par_chunks(G2_SIZE)
+        .map(|mut chunk| deserialize_g2(&mut chunk))
+        .collect()
+}
+
+// the first `num` points of `size` bytes, which the section must hold
+fn points_bytes(bytes: &[u8], num: usize, size: usize) -> IoResult<&[u8]> {
+    num.checked_mul(size)
+        .and_then(|len| bytes.get(..len))
+        .ok_or_else(|| {
+            IoError(Error::new(
+                ErrorKind::UnexpectedEof,
+                "section is too small for its points",
+            ))
+        })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use ark_bn254::{G1Projective, G2Projective};
-    use ark_crypto_primitives::snark::SNARK;
     use num_bigint::BigUint;
     use serde_json::Value;
     use std::fs::File;
-    use wasmer::Store;
 
-    use crate::circom::CircomReduction;
-    use crate::witness::WitnessCalculator;
-    use crate::{CircomBuilder, CircomConfig};
     use ark_groth16::Groth16;
-    use ark_std::rand::thread_rng;
     use num_traits::{One, Zero};
     use std::str::FromStr;
 
     use std::convert::TryFrom;
 
+    // the tests which run the witness generator of the circuit
+    #[cfg(feature = "wasmer")]
+    use {
+        crate::{witness::WitnessCalculator, CircomConfig},
+        ark_crypto_primitives::snark::SNARK,
+        ark_std::rand::thread_rng,
+        wasmer::Store,
+    };
+
     fn fq_from_str(s: &str) -> Fq {
         BigInteger256::try_from(BigUint::from_str(s).unwrap())
             .unwrap()
@@ -492,6 +1220,32 @@ mod tests {
         assert_eq!(expected, de);
     }
 
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_points_match_sequential() {
+        let buf = [g1_buf(), vec![0; G1_SIZE], g1_buf()].concat();
+        assert_eq!(
+            deserialize_g1_points_parallel(&buf, 3).unwrap(),
+            deserialize_g1_points_sequential(&buf, 3).unwrap()
+        );
+        let buf = [g2_buf(), vec![0; G2_SIZE], g2_buf()].concat();
+        assert_eq!(
+            deserialize_g2_points_parallel(&buf, 3).unwrap(),
+            deserialize_g2_points_sequential(&buf, 3).unwrap()
+        );
+    }
+
+    #[test]
+    fn forged_point_count() {
+        // a count far beyond the bytes available fails without allocating for it
+        let buf = g1_buf();
+        let err = deserialize_g1_vec(&mut &buf[..], u32::MAX).unwrap_err();
+        assert!(matches!(err, IoError(e) if e.kind() == ErrorKind::UnexpectedEof));
+        let buf = g2_buf();
+        let err = deserialize_g2_vec(&mut &buf[..], u32::MAX).unwrap_err();
+        assert!(matches!(err, IoError(e) if e.kind() == ErrorKind::UnexpectedEof));
+    }
+
     #[test]
     fn can_deser_g2() {
         let buf = g2_buf();
@@ -516,6 +1270,25 @@ mod tests {
         assert_eq!(expected, de);
     }
 
+    #[test]
+    fn non_groth16_zkey() {
+        let mut data = std::fs::read("./test-vectors/test.zkey").unwrap();
+        // the prover type is the only value of Header(1), which is the first section
+        data[24..28].copy_from_slice(&2u32.to_le_bytes());
+        let err = read_zkey(&mut std::io::Cursor::new(&data)).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("expected Groth16 zkey, found PLONK (type 2)"));
+    }
+
+    #[test]
+    fn invalid_magic() {
+        let mut data = std::fs::read("./test-vectors/test.zkey").unwrap();
+        data[..4].copy_from_slice(b"r1cs");
+        let err = read_zkey(&mut std::io::Cursor::new(&data)).unwrap_err();
+        assert!(err.to_string().contains("Invalid magic"));
+    }
+
     #[test]
     fn header() {
         // `circom --r1cs` using the below file:
@@ -843,6 +1616,260 @@ mod tests {
         G2Affine::from(G2Projective::new(x, y, z))
     }
 
+    #[test]
+    fn stream_points() {
+        let path = "./test-vectors/test.zkey";
+        let mut file = File::open(path).unwrap();
+        let (params, _matrices) = read_zkey(&mut file).unwrap();
+
+        file.rewind().unwrap();
+        let mut binfile = BinFile::new(&mut file).unwrap();
+        let a_query = binfile
+            .a_query_iter()
+            .unwrap()
+            .collect::<IoResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(a_query, params.a_query);
+        let b_g2_query = binfile
+            .b_g2_query_iter()
+            .unwrap()
+            .collect::<IoResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(b_g2_query, params.b_g2_query);
+        // the iterators can be consumed partially
+        let h = binfile.h_query_iter().unwrap().next().unwrap().unwrap();
+        assert_eq!(h, params.h_query[0]);
+        let l_query = binfile
+            .l_query_iter()
+            .unwrap()
+            .collect::<IoResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(l_query, params.l_query);
+    }
+
+    #[test]
+    fn ic_points() {
+        let path = "./test-vectors/test.zkey";
+        let mut file = File::open(path).unwrap();
+        let ic = read_ic_points(&mut file).unwrap();
+
+        file.rewind().unwrap();
+        let (params, _matrices) = read_zkey(&mut file).unwrap();
+        assert_eq!(ic.len(), 2);
+        assert_eq!(ic, params.vk.gamma_abc_g1);
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn zkey_mmap() {
+        let path = "./test-vectors/test.zkey";
+        // SAFETY: the test vectors are not modified by the tests
+        let (params, matrices) = unsafe { read_zkey_mmap(path) }.unwrap();
+        let (expected_params, expected_matrices) =
+            read_zkey(&mut File::open(path).unwrap()).unwrap();
+        assert_eq!(params, expected_params);
+        assert_eq!(matrices, expected_matrices);
+
+        // a truncated section is an error rather than a short key
+        let dir = tempfile::tempdir().unwrap();
+        let data = std::fs::read(path).unwrap();
+        let truncated_path = dir.path().join("truncated.zkey");
+        std::fs::write(&truncated_path, &data[..data.len() - 100]).unwrap();
+        assert!(unsafe { read_zkey_mmap(&truncated_path) }.is_err());
+        dir.close().unwrap();
+    }
+
+    #[test]
+    fn zkey_vk() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let vk = read_zkey_vk(&mut file).unwrap();
+        file.rewind().unwrap();
+        let (params, _) = read_zkey(&mut file).unwrap();
+        assert_eq!(vk, params.vk);
+    }
+
+    #[test]
+    fn prepared_vk() {
+        let path = "./test-vectors/test.zkey";
+        let mut file = File::open(path).unwrap();
+        let pvk = read_prepared_vk(&mut file).unwrap();
+
+        let (params, _, _, proof) = crate::mycircuit_proof();
+        assert_eq!(pvk.vk, params.vk);
+        assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &[Fr::from(33)]).unwrap());
+    }
+
+    #[test]
+    fn validate_proving_key() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let (mut params, _matrices) = read_zkey(&mut file).unwrap();
+        validate_proving_key_parallel(&params).unwrap();
+
+        // the first invalid point is reported
+        params.a_query[1] = G1Affine::new_unchecked(Fq::one(), Fq::one());
+        params.a_query[3] = G1Affine::new_unchecked(Fq::one(), Fq::one());
+        assert_eq!(
+            validate_proving_key_parallel(&params),
+            Err(InvalidPoint {
+                section: "a_query",
+                index: 1
+            })
+        );
+    }
+
+    #[test]
+    fn num_constraints() {
+        let path = "./test-vectors/test.zkey";
+        let mut file = File::open(path).unwrap();
+        let num_constraints = read_zkey_num_constraints(&mut file).unwrap();
+        assert_eq!(num_constraints, 1);
+
+        file.rewind().unwrap();
+        let (_params, matrices) = read_zkey(&mut file).unwrap();
+        assert_eq!(num_constraints, matrices.num_constraints);
+    }
+
+    #[test]
+    fn num_public() {
+        let path = "./test-vectors/test.zkey";
+        let mut file = File::open(path).unwrap();
+        assert_eq!(read_zkey_num_public(&mut file).unwrap(), 1);
+    }
+
+    #[test]
+    fn write_zkey_roundtrip() {
+        let data = std::fs::read("./test-vectors/test.zkey").unwrap();
+        let (params, matrices) = read_zkey(&mut std::io::Cursor::new(&data)).unwrap();
+
+        let mut written = Vec::new();
+        write_zkey(&params, &matrices, &mut written).unwrap();
+        let (params2, matrices2) = read_zkey(&mut std::io::Cursor::new(&written)).unwrap();
+        assert_eq!(params, params2);
+        assert_eq!(matrices, matrices2);
+
+        // everything but the ceremony information matches what snarkjs wrote
+        let contributions = BinFile::new(&mut std::io::Cursor::new(&data))
+            .unwrap()
+            .get_section(10);
+        let end = contributions.position as usize - 12;
+        assert_eq!(written[..end], data[..end]);
+    }
+
+    #[test]
+    fn checked_zkey() {
+        let mut data = std::fs::read("./test-vectors/test.zkey").unwrap();
+        let (params, _matrices) = read_zkey_checked(&mut std::io::Cursor::new(&data)).unwrap();
+
+        // corrupt the y-coordinate of the second point of `a_query`
+        let a_query = BinFile::new(&mut std::io::Cursor::new(&data))
+            .unwrap()
+            .get_section(5);
+        data[a_query.position as usize + 64 + 32] ^= 1;
+
+        let (corrupted, _matrices) = read_zkey(&mut std::io::Cursor::new(&data)).unwrap();
+        assert_ne!(corrupted.a_query[1], params.a_query[1]);
+        let err = read_zkey_checked(&mut std::io::Cursor::new(&data)).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("invalid point at index 1 of `a_query`"));
+    }
+
+    #[cfg(feature = "wasmer")]
+    #[tokio::test]
+    async fn setup_and_write() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        let mut zkey = Vec::new();
+        setup_and_write_zkey(&builder, &mut thread_rng(), &mut zkey).unwrap();
+        let (params, matrices) = read_zkey(&mut std::io::Cursor::new(zkey)).unwrap();
+
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        let circom = builder.build().unwrap();
+        let inputs = circom.get_public_inputs().unwrap();
+
+        let full_assignment = circom.witness.unwrap();
+        let proof =
+            crate::groth16_prove(&params, &matrices, &full_assignment, &mut thread_rng()).unwrap();
+
+        let pvk = Groth16::<Bn254>::process_vk(&params.vk).unwrap();
+        assert!(Groth16::<Bn254>::verify_with_processed_vk(&pvk, &inputs, &proof).unwrap());
+    }
+
+    #[cfg(feature = "wasmer")]
+    #[tokio::test]
+    async fn parameters_from_seed() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let builder = CircomBuilder::new(cfg);
+
+        let params = generate_parameters_from_seed(&builder, 42).unwrap();
+        assert_eq!(params, generate_parameters_from_seed(&builder, 42).unwrap());
+        assert_ne!(
+            params.vk,
+            generate_parameters_from_seed(&builder, 43).unwrap().vk
+        );
+        // the key of a seed is pinned, as it is meant to be checked in
+        assert_eq!(
+            params.vk.alpha_g1.x.to_string(),
+            "13094246696466241330879613155006547391338450027288268509093648962262180137797"
+        );
+    }
+
+    #[test]
+    fn ceremony_info() {
+        let path = "./test-vectors/test.zkey";
+        let mut file = File::open(path).unwrap();
+        let info = read_zkey_ceremony_info(&mut file).unwrap();
+        assert_eq!(info.cs_hash[..4], [0x42, 0x89, 0x91, 0x8f]);
+        assert!(info.contributions.is_empty());
+    }
+
+    #[test]
+    fn ceremony_info_beacon() {
+        let mut section = vec![7u8; 64];
+        section.extend(1u32.to_le_bytes());
+        // delta after, g1_s, g1_sx, g2_spx
+        section.extend(g1_buf().repeat(3));
+        section.extend(g2_buf());
+        section.extend([9u8; 64]);
+        section.extend(1u32.to_le_bytes());
+        let mut params = vec![1, 6];
+        params.extend(b"beacon");
+        params.extend([2, 10]);
+        params.extend([3, 32]);
+        params.extend([5u8; 32]);
+        section.extend((params.len() as u32).to_le_bytes());
+        section.extend(params);
+
+        let mut zkey = b"zkey".to_vec();
+        zkey.extend(1u32.to_le_bytes());
+        zkey.extend(1u32.to_le_bytes());
+        zkey.extend(10u32.to_le_bytes());
+        zkey.extend((section.len() as u64).to_le_bytes());
+        zkey.extend(section);
+
+        let info = read_zkey_ceremony_info(&mut std::io::Cursor::new(zkey)).unwrap();
+        assert_eq!(info.cs_hash, [7u8; 64]);
+        assert_eq!(info.contributions.len(), 1);
+
+        let contribution = &info.contributions[0];
+        assert_eq!(contribution.delta_after, g1_one());
+        assert_eq!(contribution.transcript, [9u8; 64]);
+        assert_eq!(contribution.contribution_type, 1);
+        assert_eq!(contribution.name.as_deref(), Some("beacon"));
+        assert_eq!(contribution.num_iterations_exp, Some(10));
+        assert_eq!(contribution.beacon_hash, Some(vec![5u8; 32]));
+    }
+
+    #[cfg(feature = "wasmer")]
     #[tokio::test]
     async fn verify_proof_with_zkey_with_r1cs() {
         let path = "./test-vectors/test.zkey";
@@ -858,6 +1885,7 @@ mod tests {
         builder.push_input("a", 3);
         builder.push_input("b", 11);
 
+        check_zkey_matches_r1cs(&params, &builder.cfg.r1cs).unwrap();
         let circom = builder.build().unwrap();
 
         let inputs = circom.get_public_inputs().unwrap();
@@ -872,6 +1900,26 @@ mod tests {
         assert!(verified);
     }
 
+    #[test]
+    fn zkey_matches_r1cs() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let (params, _matrices) = read_zkey(&mut file).unwrap();
+
+        let file = File::open("./test-vectors/mycircuit.r1cs").unwrap();
+        let r1cs = crate::circom::R1CSFile::<Fr>::new(file).unwrap().into();
+        check_zkey_matches_r1cs(&params, &r1cs).unwrap();
+
+        let file =
+            File::open("./test-vectors/complex-circuit/complex-circuit-10000-10000.r1cs").unwrap();
+        let r1cs: R1CS<Fr> = crate::circom::R1CSFile::<Fr>::new(file).unwrap().into();
+        let err = check_zkey_matches_r1cs(&params, &r1cs).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("zkey n_vars (4) != r1cs n_wires ({})", r1cs.num_variables)
+        );
+    }
+
+    #[cfg(feature = "wasmer")]
     #[tokio::test]
     async fn verify_proof_with_zkey_without_r1cs() {
         let path = "./test-vectors/test.zkey";
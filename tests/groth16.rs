@@ -1,3 +1,5 @@
+#![cfg(feature = "wasmer")]
+
 use ark_circom::{CircomBuilder, CircomConfig};
 use ark_std::rand::thread_rng;
 use color_eyre::Result;
@@ -107,3 +109,39 @@ async fn witness_generation_circom2() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn verify_with_snarkjs_vk() -> Result<()> {
+    use ark_circom::{groth16_prove, read_zkey, snarkjs};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+    // prove natively with the proving key of the zkey. This repeats the `mycircuit_proof`
+    // fixture of the unit tests, which integration tests cannot use.
+    let mut file = std::fs::File::open("./test-vectors/test.zkey")?;
+    let (params, matrices) = read_zkey(&mut file)?;
+    // witness of mycircuit for a = 3, b = 11
+    let full_assignment = [1, 33, 3, 11].map(Fr::from);
+    let proof = groth16_prove(&params, &matrices, &full_assignment, &mut thread_rng())?;
+
+    // round trip the proof through the arkworks serialization
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes)?;
+    let proof = ark_groth16::Proof::<Bn254>::deserialize_compressed(&bytes[..])?;
+
+    // and verify it with the key exported by snarkjs
+    let json = std::fs::read_to_string("./test-vectors/verification_key.json")?;
+    let vk = snarkjs::verifying_key_from_json(&json)?;
+    let pvk = GrothBn::process_vk(&vk)?;
+    assert!(GrothBn::verify_with_processed_vk(
+        &pvk,
+        &[Fr::from(33)],
+        &proof
+    )?);
+    assert!(!GrothBn::verify_with_processed_vk(
+        &pvk,
+        &[Fr::from(34)],
+        &proof
+    )?);
+
+    Ok(())
+}
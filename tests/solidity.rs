@@ -1,4 +1,7 @@
-use ark_circom::{ethereum, CircomBuilder, CircomConfig};
+// the contract bindings of ethers take its U256
+#![cfg(all(feature = "ethereum", feature = "wasmer"))]
+
+use ark_circom::{ethereum, generate_parameters_from_seed, CircomBuilder, CircomConfig};
 use ark_std::rand::thread_rng;
 use color_eyre::Result;
 
@@ -7,13 +10,18 @@ use ark_crypto_primitives::snark::SNARK;
 use ark_groth16::Groth16;
 
 use ethers::{
-    contract::ContractError,
+    contract::{ContractError, ContractFactory},
     prelude::abigen,
     providers::{Http, Middleware, Provider},
-    utils::Anvil,
+    solc::Solc,
+    types::{transaction::eip2718::TypedTransaction, Bytes, TransactionRequest},
+    utils::{id, Anvil},
 };
 use std::{convert::TryFrom, sync::Arc};
 
+// seed of the setup of the verifiers
+const SEED: u64 = 42;
+
 #[tokio::test]
 async fn solidity_verifier() -> Result<()> {
     let cfg = CircomConfig::<Fr>::new(
@@ -24,15 +32,13 @@ async fn solidity_verifier() -> Result<()> {
     builder.push_input("a", 3);
     builder.push_input("b", 11);
 
-    // create an empty instance for setting it up
-    let circom = builder.setup();
-
-    let mut rng = thread_rng();
-    let params = Groth16::<Bn254>::generate_random_parameters_with_reduction(circom, &mut rng)?;
+    // use a fixed key so that the verifier is the same on every run
+    let params = generate_parameters_from_seed(&builder, SEED)?;
 
     let circom = builder.build()?;
     let inputs = circom.get_public_inputs().unwrap();
 
+    let mut rng = thread_rng();
     let proof = Groth16::<Bn254>::prove(&params, circom, &mut rng)?;
 
     // launch the network & compile the verifier
@@ -57,9 +63,68 @@ async fn solidity_verifier() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn exported_solidity_verifier() -> Result<()> {
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+    let mut builder = CircomBuilder::new(cfg);
+    builder.push_input("a", 3);
+    builder.push_input("b", 11);
+
+    let params = generate_parameters_from_seed(&builder, SEED)?;
+
+    let circom = builder.build()?;
+    let inputs = circom.get_public_inputs().unwrap();
+    let mut rng = thread_rng();
+    let proof = Groth16::<Bn254>::prove(&params, circom, &mut rng)?;
+
+    // compile the verifier generated for the key
+    let path = std::env::temp_dir().join("ark_circom_groth16_verifier.sol");
+    std::fs::write(&path, ethereum::export_solidity_verifier(&params.vk))?;
+    let output = Solc::default().compile_source(&path)?;
+    let (abi, bytecode, _) = output
+        .find("Groth16Verifier")
+        .expect("the verifier is compiled")
+        .into_parts_or_default();
+
+    let anvil = Anvil::new().spawn();
+    let acc = anvil.addresses()[0];
+    let provider = Provider::<Http>::try_from(anvil.endpoint())?;
+    let provider = Arc::new(provider.with_sender(acc));
+
+    let contract = ContractFactory::new(abi, bytecode, provider.clone())
+        .deploy(())?
+        .send()
+        .await?;
+
+    // call verifyProof with the calldata of the proof
+    let selector = id("verifyProof(uint256[2],uint256[2][2],uint256[2],uint256[1])");
+    let verify = |inputs: &[Fr]| -> TypedTransaction {
+        let proof: ethereum::Proof = proof.clone().into();
+        let calldata = ethereum::to_calldata(&proof, &inputs.into());
+        let data: Bytes = [&selector[..], &calldata].concat().into();
+        TransactionRequest::new()
+            .to(contract.address())
+            .data(data)
+            .into()
+    };
+
+    let res = provider.call(&verify(&inputs), None).await?;
+    assert_eq!(res.last(), Some(&1));
+
+    let res = provider.call(&verify(&[Fr::from(34)]), None).await?;
+    assert_eq!(res.last(), Some(&0));
+
+    Ok(())
+}
+
 // We need to implement the conversion from the Ark-Circom's internal Ethereum types to
 // the ones expected by the abigen'd types. Could we maybe provide a convenience
 // macro for these, given that there's room for implementation error?
+// The verifier of the artifact takes the verifying key as an argument, so the artifact does
+// not depend on the setup and only the key changes with the seed.
 abigen!(Groth16Verifier, "./tests/verifier_artifact.json");
 impl From<ethereum::G1> for G1Point {
     fn from(src: ethereum::G1) -> Self {